@@ -0,0 +1,481 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tokio::time::{sleep, Duration};
+
+use crate::{stress, SystemStats};
+
+/// Métricas del sistema que puede usar una regla, además de `contenedores:<categoria>`
+pub const METRICAS: [&str; 5] = ["cpu_usada", "ram_ocupada", "ram_libre", "ram_total", "ram_porcentaje"];
+// Con más reintentos la espera exponencial pasa de horas
+const MAX_REINTENTOS: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Operador {
+    #[serde(rename = ">")]
+    Mayor,
+    #[serde(rename = ">=")]
+    MayorIgual,
+    #[serde(rename = "<")]
+    Menor,
+    #[serde(rename = "<=")]
+    MenorIgual,
+    #[serde(rename = "==")]
+    Igual,
+    #[serde(rename = "!=")]
+    Distinto,
+}
+
+impl Operador {
+    fn cumple(self, valor: f64, umbral: f64) -> bool {
+        match self {
+            Operador::Mayor => valor > umbral,
+            Operador::MayorIgual => valor >= umbral,
+            Operador::Menor => valor < umbral,
+            Operador::MenorIgual => valor <= umbral,
+            Operador::Igual => valor == umbral,
+            Operador::Distinto => valor != umbral,
+        }
+    }
+}
+
+/// Una regla del archivo de configuración, por ejemplo:
+///
+/// ```toml
+/// [[alertas.reglas]]
+/// nombre = "sin_contenedores_ram"
+/// metrica = "contenedores:ram"
+/// operador = "=="
+/// umbral = 0
+/// durante_segundos = 60
+/// ```
+///
+/// Métricas: `cpu_usada`, `ram_ocupada`, `ram_libre`, `ram_total`,
+/// `ram_porcentaje` y `contenedores:<categoria>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReglaAlerta {
+    pub nombre: String,
+    pub metrica: String,
+    pub operador: Operador,
+    pub umbral: f64,
+    /// Muestras seguidas que se debe cumplir la condición antes de disparar
+    #[serde(default)]
+    pub durante_muestras: u32,
+    /// Segundos que se debe cumplir la condición antes de disparar
+    #[serde(default)]
+    pub durante_segundos: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigAlertas {
+    pub reglas: Vec<ReglaAlerta>,
+    pub webhooks: Vec<String>,
+    pub reintentos: u32,
+    pub espera_reintento_ms: u64,
+}
+
+impl Default for ConfigAlertas {
+    fn default() -> Self {
+        ConfigAlertas {
+            reglas: Vec::new(),
+            webhooks: Vec::new(),
+            reintentos: 3,
+            espera_reintento_ms: 500,
+        }
+    }
+}
+
+impl ConfigAlertas {
+    pub fn validar(&self) -> Result<(), String> {
+        if self.reintentos > MAX_REINTENTOS {
+            return Err(format!("alertas.reintentos debe ser como mucho {}", MAX_REINTENTOS));
+        }
+        // El estado de cada regla se guarda por nombre
+        let mut nombres = HashSet::new();
+        for regla in &self.reglas {
+            if !nombres.insert(regla.nombre.as_str()) {
+                return Err(format!("Hay dos reglas con el nombre '{}'", regla.nombre));
+            }
+            let metrica_valida = match regla.metrica.strip_prefix("contenedores:") {
+                Some(categoria) => stress::CATEGORIAS.contains(&categoria),
+                None => METRICAS.contains(&regla.metrica.as_str()),
+            };
+            if !metrica_valida {
+                return Err(format!(
+                    "regla '{}': métrica '{}' desconocida, use una de {:?} o contenedores:<{}>",
+                    regla.nombre,
+                    regla.metrica,
+                    METRICAS,
+                    stress::CATEGORIAS.join("|")
+                ));
+            }
+            if !regla.umbral.is_finite() {
+                return Err(format!("regla '{}': el umbral debe ser un número finito", regla.nombre));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EstadoAlerta {
+    Inactiva,
+    Pendiente,
+    Disparada,
+    Resuelta,
+}
+
+/// Lo que se envía a los webhooks en cada cambio de estado
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificacionAlerta {
+    pub regla: String,
+    pub metrica: String,
+    pub operador: Operador,
+    pub umbral: f64,
+    pub valor: f64,
+    pub estado: EstadoAlerta,
+    pub timestamp: u64,
+}
+
+impl NotificacionAlerta {
+    /// Categoría de la métrica si es un conteo de contenedores
+    pub fn categoria(&self) -> Option<&str> {
+        self.metrica.strip_prefix("contenedores:")
+    }
+}
+
+/// Lo que el monitor leyó en un ciclo. `categorias` es None si Docker no respondió.
+pub struct MuestraAlertas<'a> {
+    pub timestamp: u64,
+    pub system: Option<&'a SystemStats>,
+    pub categorias: Option<&'a HashMap<String, usize>>,
+}
+
+impl MuestraAlertas<'_> {
+    fn valor(&self, metrica: &str) -> Option<f64> {
+        if let Some(categoria) = metrica.strip_prefix("contenedores:") {
+            return self.categorias.map(|c| c.get(categoria).copied().unwrap_or(0) as f64);
+        }
+
+        let system = self.system?;
+        match metrica {
+            "cpu_usada" => Some(system.cpu_usada as f64),
+            "ram_ocupada" => Some(system.ram_ocupada as f64),
+            "ram_libre" => Some(system.ram_libre as f64),
+            "ram_total" => Some(system.ram_total as f64),
+            "ram_porcentaje" if system.ram_total > 0 => {
+                Some(system.ram_ocupada as f64 * 100.0 / system.ram_total as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Seguimiento {
+    estado: EstadoAlerta,
+    muestras: u32,
+    desde: u64,
+}
+
+pub struct MotorAlertas {
+    config: ConfigAlertas,
+    seguimiento: Mutex<HashMap<String, Seguimiento>>,
+    cliente: reqwest::Client,
+}
+
+impl MotorAlertas {
+    pub fn new(config: ConfigAlertas) -> Self {
+        let cliente = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(5))
+            .build()
+            .expect("No se pudo crear el cliente HTTP de alertas");
+
+        MotorAlertas {
+            config,
+            seguimiento: Mutex::new(HashMap::new()),
+            cliente,
+        }
+    }
+
+    /// Evalúa todas las reglas con la muestra y devuelve los cambios de estado.
+    /// Si la métrica de una regla no está en la muestra, la regla se queda como está.
+    pub fn evaluar(&self, muestra: &MuestraAlertas) -> Vec<NotificacionAlerta> {
+        let mut seguimiento = self.seguimiento.lock().unwrap();
+        let mut cambios = Vec::new();
+
+        for regla in &self.config.reglas {
+            let valor = match muestra.valor(&regla.metrica) {
+                Some(valor) => valor,
+                None => continue,
+            };
+
+            let actual = seguimiento.entry(regla.nombre.clone()).or_insert(Seguimiento {
+                estado: EstadoAlerta::Inactiva,
+                muestras: 0,
+                desde: muestra.timestamp,
+            });
+
+            let nuevo_estado = if regla.operador.cumple(valor, regla.umbral) {
+                if actual.muestras == 0 {
+                    actual.desde = muestra.timestamp;
+                }
+                actual.muestras += 1;
+
+                let muestras_ok = actual.muestras >= regla.durante_muestras.max(1);
+                let segundos_ok = muestra.timestamp.saturating_sub(actual.desde) >= regla.durante_segundos;
+                if muestras_ok && segundos_ok {
+                    EstadoAlerta::Disparada
+                } else {
+                    EstadoAlerta::Pendiente
+                }
+            } else {
+                actual.muestras = 0;
+                match actual.estado {
+                    EstadoAlerta::Disparada => EstadoAlerta::Resuelta,
+                    _ => EstadoAlerta::Inactiva,
+                }
+            };
+
+            if nuevo_estado != actual.estado && nuevo_estado != EstadoAlerta::Inactiva {
+                cambios.push(NotificacionAlerta {
+                    regla: regla.nombre.clone(),
+                    metrica: regla.metrica.clone(),
+                    operador: regla.operador,
+                    umbral: regla.umbral,
+                    valor,
+                    estado: nuevo_estado,
+                    timestamp: muestra.timestamp,
+                });
+            }
+
+            // Una alerta resuelta vuelve a quedar inactiva para el siguiente ciclo
+            actual.estado = match nuevo_estado {
+                EstadoAlerta::Resuelta => EstadoAlerta::Inactiva,
+                estado => estado,
+            };
+        }
+
+        cambios
+    }
+
+    /// Envía la notificación a todos los webhooks sin bloquear al monitor
+    pub fn notificar(&self, notificacion: &NotificacionAlerta) {
+        log::warn!(
+            "Alerta '{}' {:?}: {} = {} (umbral {:?} {})",
+            notificacion.regla, notificacion.estado, notificacion.metrica,
+            notificacion.valor, notificacion.operador, notificacion.umbral
+        );
+
+        for url in &self.config.webhooks {
+            tokio::spawn(enviar_webhook(
+                self.cliente.clone(),
+                url.clone(),
+                notificacion.clone(),
+                self.config.reintentos,
+                Duration::from_millis(self.config.espera_reintento_ms),
+            ));
+        }
+    }
+}
+
+async fn enviar_webhook(cliente: reqwest::Client, url: String, notificacion: NotificacionAlerta, reintentos: u32, espera: Duration) {
+    for intento in 0..=reintentos {
+        match cliente.post(&url).json(&notificacion).send().await {
+            Ok(respuesta) if respuesta.status().is_success() => return,
+            Ok(respuesta) => log::warn!("Webhook {} respondió {} (intento {})", url, respuesta.status(), intento + 1),
+            Err(e) => log::warn!("Error al enviar webhook a {}: {} (intento {})", url, e, intento + 1),
+        }
+
+        if intento < reintentos {
+            // Espera exponencial entre reintentos
+            sleep(espera.saturating_mul(2u32.saturating_pow(intento))).await;
+        }
+    }
+
+    log::error!("No se pudo entregar la alerta '{}' a {}", notificacion.regla, url);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regla(durante_muestras: u32, durante_segundos: u64) -> ReglaAlerta {
+        ReglaAlerta {
+            nombre: "cpu_alta".to_string(),
+            metrica: "cpu_usada".to_string(),
+            operador: Operador::Mayor,
+            umbral: 90.0,
+            durante_muestras,
+            durante_segundos,
+        }
+    }
+
+    fn motor(reglas: Vec<ReglaAlerta>) -> MotorAlertas {
+        MotorAlertas::new(ConfigAlertas { reglas, ..ConfigAlertas::default() })
+    }
+
+    fn evaluar(motor: &MotorAlertas, timestamp: u64, cpu: u8) -> Vec<EstadoAlerta> {
+        let system = SystemStats { ram_total: 100, ram_libre: 50, ram_ocupada: 50, cpu_usada: cpu };
+        let muestra = MuestraAlertas { timestamp, system: Some(&system), categorias: None };
+        motor.evaluar(&muestra).into_iter().map(|n| n.estado).collect()
+    }
+
+    #[test]
+    fn pendiente_disparada_resuelta_por_muestras() {
+        let motor = motor(vec![regla(3, 0)]);
+        assert_eq!(evaluar(&motor, 0, 95), vec![EstadoAlerta::Pendiente]);
+        // Seguir pendiente no vuelve a notificar
+        assert!(evaluar(&motor, 10, 95).is_empty());
+        assert_eq!(evaluar(&motor, 20, 95), vec![EstadoAlerta::Disparada]);
+        assert!(evaluar(&motor, 30, 99).is_empty());
+        assert_eq!(evaluar(&motor, 40, 50), vec![EstadoAlerta::Resuelta]);
+        assert!(evaluar(&motor, 50, 50).is_empty());
+    }
+
+    #[test]
+    fn pendiente_que_no_llega_a_disparar_no_se_resuelve() {
+        let motor = motor(vec![regla(3, 0)]);
+        assert_eq!(evaluar(&motor, 0, 95), vec![EstadoAlerta::Pendiente]);
+        assert!(evaluar(&motor, 10, 10).is_empty());
+        // El conteo empieza de nuevo
+        assert_eq!(evaluar(&motor, 20, 95), vec![EstadoAlerta::Pendiente]);
+        assert!(evaluar(&motor, 30, 95).is_empty());
+        assert_eq!(evaluar(&motor, 40, 95), vec![EstadoAlerta::Disparada]);
+    }
+
+    #[test]
+    fn dispara_despues_de_los_segundos() {
+        let motor = motor(vec![regla(0, 60)]);
+        assert_eq!(evaluar(&motor, 100, 95), vec![EstadoAlerta::Pendiente]);
+        assert!(evaluar(&motor, 150, 95).is_empty());
+        assert_eq!(evaluar(&motor, 160, 95), vec![EstadoAlerta::Disparada]);
+    }
+
+    #[test]
+    fn sin_la_metrica_la_regla_no_cambia() {
+        let mut sin_ram = regla(1, 0);
+        sin_ram.metrica = "contenedores:ram".to_string();
+        sin_ram.operador = Operador::Igual;
+        sin_ram.umbral = 0.0;
+        let motor = motor(vec![sin_ram]);
+        let muestra = MuestraAlertas { timestamp: 0, system: None, categorias: None };
+        assert!(motor.evaluar(&muestra).is_empty());
+
+        let categorias = HashMap::from([("cpu".to_string(), 2)]);
+        let muestra = MuestraAlertas { timestamp: 10, system: None, categorias: Some(&categorias) };
+        assert_eq!(motor.evaluar(&muestra)[0].estado, EstadoAlerta::Disparada);
+    }
+
+    #[test]
+    fn validar_rechaza_metricas_desconocidas_y_muchos_reintentos() {
+        let mut config = ConfigAlertas { reglas: vec![regla(1, 0)], ..ConfigAlertas::default() };
+        assert!(config.validar().is_ok());
+
+        config.reglas[0].metrica = "contenedores:gpu".to_string();
+        assert!(config.validar().unwrap_err().contains("contenedores:gpu"));
+        config.reglas[0].metrica = "cpu".to_string();
+        assert!(config.validar().is_err());
+
+        config.reglas[0].metrica = "ram_porcentaje".to_string();
+        config.reintentos = 32;
+        assert!(config.validar().unwrap_err().contains("reintentos"));
+    }
+
+    #[test]
+    fn validar_rechaza_nombres_repetidos() {
+        let mut otra = regla(1, 0);
+        otra.metrica = "ram_porcentaje".to_string();
+        let config = ConfigAlertas { reglas: vec![regla(1, 0), otra], ..ConfigAlertas::default() };
+        assert!(config.validar().unwrap_err().contains("dos reglas con el nombre 'cpu_alta'"));
+    }
+
+    /// Receptor HTTP local que responde `status` a todo y guarda el body de cada petición
+    async fn receptor(status: u16) -> (String, std::sync::Arc<Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/alertas", listener.local_addr().unwrap());
+        let bodies = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let recibidos = bodies.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut datos = Vec::new();
+                let mut buffer = [0u8; 4096];
+                // Se lee hasta tener los headers y todo el body que dice Content-Length
+                let body = loop {
+                    let leidos = socket.read(&mut buffer).await.unwrap();
+                    if leidos == 0 {
+                        break None;
+                    }
+                    datos.extend_from_slice(&buffer[..leidos]);
+                    let texto = String::from_utf8_lossy(&datos).to_string();
+                    if let Some(fin) = texto.find("\r\n\r\n") {
+                        let largo = texto[..fin]
+                            .lines()
+                            .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                            .unwrap_or(0);
+                        if datos.len() >= fin + 4 + largo {
+                            break Some(String::from_utf8_lossy(&datos[fin + 4..fin + 4 + largo]).to_string());
+                        }
+                    }
+                };
+                if let Some(body) = body {
+                    recibidos.lock().unwrap().push(body);
+                }
+                let respuesta = format!("HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                let _ = socket.write_all(respuesta.as_bytes()).await;
+            }
+        });
+        (url, bodies)
+    }
+
+    fn notificacion() -> NotificacionAlerta {
+        NotificacionAlerta {
+            regla: "cpu_alta".to_string(),
+            metrica: "cpu_usada".to_string(),
+            operador: Operador::Mayor,
+            umbral: 90.0,
+            valor: 95.0,
+            estado: EstadoAlerta::Disparada,
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    #[tokio::test]
+    async fn el_webhook_recibe_la_notificacion_en_json() {
+        let (url, bodies) = receptor(200).await;
+        enviar_webhook(reqwest::Client::new(), url, notificacion(), 3, Duration::from_millis(1)).await;
+
+        let bodies = bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 1);
+        let json: serde_json::Value = serde_json::from_str(&bodies[0]).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "regla": "cpu_alta",
+                "metrica": "cpu_usada",
+                "operador": ">",
+                "umbral": 90.0,
+                "valor": 95.0,
+                "estado": "disparada",
+                "timestamp": 1_700_000_000u64,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn con_error_del_receptor_se_reintenta() {
+        let (url, bodies) = receptor(500).await;
+        enviar_webhook(reqwest::Client::new(), url, notificacion(), 2, Duration::from_millis(1)).await;
+
+        // El primer intento más dos reintentos, todos con el mismo body
+        let bodies = bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 3);
+        assert!(bodies.iter().all(|b| b == &bodies[0]));
+    }
+}
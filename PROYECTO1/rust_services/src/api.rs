@@ -0,0 +1,371 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, Responder};
+use bollard::container::{KillContainerOptions, RemoveContainerOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::dashboard::ContenedorVivo;
+use crate::stress;
+use crate::{clasificar_contenedor, enriquecer_contenedores, enviar_container_logs, leer_sysinfo, AppState, ContainerLog};
+
+const POR_PAGINA_DEFECTO: usize = 50;
+const POR_PAGINA_MAX: usize = 500;
+// Lo que se puede hacer con `POST /api/containers/{id}/{accion}`, `remove` es el DELETE
+const ACCIONES: [&str; 5] = ["stop", "kill", "pause", "unpause", "remove"];
+
+/// Query de `GET /api/containers`.
+/// `orden` es un campo de `ContainerInfo`, con `-` adelante se ordena de mayor a menor.
+/// Ej: `/api/containers?categoria=ram&min_rss=1024&orden=-memory_rss&pagina=2&por_pagina=10`
+#[derive(Debug, Deserialize)]
+pub struct FiltroContenedores {
+    categoria: Option<String>,
+    min_cpu: Option<u8>,
+    min_rss: Option<u64>,
+    orden: Option<String>,
+    pagina: Option<usize>,
+    por_pagina: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct PaginaContenedores {
+    total: usize,
+    pagina: usize,
+    por_pagina: usize,
+    containers: Vec<ContenedorVivo>,
+}
+
+fn error_json(status: StatusCode, mensaje: String) -> HttpResponse {
+    HttpResponse::build(status).json(json!({ "error": mensaje }))
+}
+
+/// `GET /api/system`
+pub async fn get_system() -> impl Responder {
+    match leer_sysinfo() {
+        Ok(sys_info) => HttpResponse::Ok().json(sys_info.system),
+        Err(e) => error_json(StatusCode::SERVICE_UNAVAILABLE, format!("No se pudo leer la información del sistema: {}", e)),
+    }
+}
+
+/// `GET /api/containers`
+pub async fn get_containers(filtro: web::Query<FiltroContenedores>) -> impl Responder {
+    let sys_info = match leer_sysinfo() {
+        Ok(sys_info) => sys_info,
+        Err(e) => return error_json(StatusCode::SERVICE_UNAVAILABLE, format!("No se pudo leer la información del sistema: {}", e)),
+    };
+
+    match paginar(enriquecer_contenedores(sys_info.containers), &filtro) {
+        Ok(pagina) => HttpResponse::Ok().json(pagina),
+        Err(e) => error_json(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+/// Aplica los filtros, el orden y la página de `GET /api/containers`
+fn paginar(containers: Vec<ContenedorVivo>, filtro: &FiltroContenedores) -> Result<PaginaContenedores, String> {
+    let mut containers: Vec<ContenedorVivo> = containers
+        .into_iter()
+        .filter(|c| filtro.categoria.as_ref().is_none_or(|cat| c.categoria.as_ref() == Some(cat)))
+        .filter(|c| filtro.min_cpu.is_none_or(|min| c.info.cpu_percent >= min))
+        .filter(|c| filtro.min_rss.is_none_or(|min| c.info.memory_rss >= min))
+        .collect();
+
+    if let Some(orden) = &filtro.orden {
+        let (campo, descendente) = match orden.strip_prefix('-') {
+            Some(campo) => (campo, true),
+            None => (orden.as_str(), false),
+        };
+
+        match campo {
+            "pid" => containers.sort_by_key(|c| c.info.pid),
+            "name" => containers.sort_by(|a, b| a.info.name.cmp(&b.info.name)),
+            "cpu_percent" => containers.sort_by_key(|c| c.info.cpu_percent),
+            "memory_percent" => containers.sort_by_key(|c| c.info.memory_percent),
+            "memory_rss" => containers.sort_by_key(|c| c.info.memory_rss),
+            "virtual_memory" => containers.sort_by_key(|c| c.info.virtual_memory),
+            "disk_usage" => containers.sort_by_key(|c| c.info.disk_usage),
+            "io_read_bytes" => containers.sort_by_key(|c| c.info.io_read_bytes),
+            "io_write_bytes" => containers.sort_by_key(|c| c.info.io_write_bytes),
+            _ => return Err(format!("No se puede ordenar por '{}'", campo)),
+        }
+
+        if descendente {
+            containers.reverse();
+        }
+    }
+
+    let por_pagina = filtro.por_pagina.unwrap_or(POR_PAGINA_DEFECTO).clamp(1, POR_PAGINA_MAX);
+    let pagina = filtro.pagina.unwrap_or(1).max(1);
+    let total = containers.len();
+
+    let containers = containers
+        .into_iter()
+        .skip((pagina - 1).saturating_mul(por_pagina))
+        .take(por_pagina)
+        .collect();
+
+    Ok(PaginaContenedores { total, pagina, por_pagina, containers })
+}
+
+/// `POST /api/containers/{id}/{accion}` con accion `stop`, `kill`, `pause` o `unpause`
+pub async fn controlar_contenedor(data: web::Data<AppState>, path: web::Path<(String, String)>) -> impl Responder {
+    let (id, accion) = path.into_inner();
+    ejecutar_accion(&data, &id, &accion).await
+}
+
+/// `DELETE /api/containers/{id}`
+pub async fn delete_container(data: web::Data<AppState>, id: web::Path<String>) -> impl Responder {
+    ejecutar_accion(&data, &id, "remove").await
+}
+
+async fn ejecutar_accion(data: &AppState, id: &str, accion: &str) -> HttpResponse {
+    // Se revisa antes de preguntarle a Docker por el contenedor
+    if !ACCIONES.contains(&accion) {
+        return accion_desconocida(accion);
+    }
+    let docker = &data.docker;
+
+    // Solo se controlan contenedores de stress, así no se toca el logger ni otros servicios
+    let name = match docker.inspect_container(id, None).await {
+        Ok(info) => info.name.unwrap_or_default().trim_start_matches('/').to_string(),
+        Err(e) => return error_docker(e),
+    };
+    let category = match clasificar_contenedor(&name) {
+        Some(category) => category,
+        None => return error_json(StatusCode::FORBIDDEN, format!("'{}' no es un contenedor de stress", name)),
+    };
+
+    let resultado = match accion {
+        "stop" => docker.stop_container(id, None).await.map(|_| "detenido"),
+        "kill" => docker
+            .kill_container(id, Some(KillContainerOptions { signal: "SIGKILL" }))
+            .await
+            .map(|_| "matado"),
+        "pause" => docker.pause_container(id).await.map(|_| "pausado"),
+        "unpause" => docker.unpause_container(id).await.map(|_| "reanudado"),
+        "remove" => docker
+            .remove_container(id, Some(RemoveContainerOptions { force: true, ..Default::default() }))
+            .await
+            .map(|_| "eliminado"),
+        _ => return accion_desconocida(accion),
+    };
+
+    let action = match resultado {
+        Ok(action) => action,
+        Err(e) => return error_docker(e),
+    };
+
+    log::info!("Contenedor {} de categoria {} {} desde la API", name, category, action);
+
+    if action == "eliminado" {
+        data.sesion.registrar_eliminado(&category);
+    }
+
+    let container_log = ContainerLog {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        category,
+        name,
+        action: action.to_string(),
+    };
+
+    data.monitor.registrar_eventos(std::slice::from_ref(&container_log));
+    let logs = vec![container_log.clone()];
+    if let Ok(Err(e)) = web::block(move || enviar_container_logs(&logs)).await {
+        log::error!("No se pudo escribir el log del contenedor: {}", e);
+    }
+
+    HttpResponse::Ok().json(json!({ "id": id, "log": container_log }))
+}
+
+fn accion_desconocida(accion: &str) -> HttpResponse {
+    error_json(StatusCode::BAD_REQUEST, format!("Acción desconocida '{}', use una de {:?}", accion, ACCIONES))
+}
+
+/// Usa el mismo código que respondió Docker (404, 409, ...)
+fn status_docker(e: bollard::errors::Error) -> (StatusCode, String) {
+    match e {
+        bollard::errors::Error::DockerResponseServerError { status_code, message } => {
+            (StatusCode::from_u16(status_code).unwrap_or(StatusCode::BAD_GATEWAY), message)
+        }
+        e => (StatusCode::BAD_GATEWAY, format!("Error al comunicarse con Docker: {}", e)),
+    }
+}
+
+fn error_docker(e: bollard::errors::Error) -> HttpResponse {
+    let (status, mensaje) = status_docker(e);
+    error_json(status, mensaje)
+}
+
+/// Body de `POST /api/stress`, solo `categoria` es obligatoria
+#[derive(Debug, Deserialize)]
+pub struct SolicitudStress {
+    categoria: String,
+    #[serde(default = "duracion_defecto")]
+    duracion_segundos: u32,
+    #[serde(default = "uno")]
+    intensidad: u32,
+    #[serde(default = "uno")]
+    cantidad: u32,
+}
+
+fn duracion_defecto() -> u32 {
+    30
+}
+
+fn uno() -> u32 {
+    1
+}
+
+/// Revisa los límites de la solicitud y devuelve los argumentos de stress
+fn validar_stress(solicitud: &SolicitudStress) -> Result<Vec<String>, String> {
+    if solicitud.cantidad == 0 || solicitud.cantidad > stress::MAX_CANTIDAD {
+        return Err(format!("cantidad debe estar entre 1 y {}", stress::MAX_CANTIDAD));
+    }
+    if solicitud.intensidad == 0 || solicitud.intensidad > stress::MAX_INTENSIDAD {
+        return Err(format!("intensidad debe estar entre 1 y {}", stress::MAX_INTENSIDAD));
+    }
+    if solicitud.duracion_segundos == 0 || solicitud.duracion_segundos > stress::MAX_DURACION {
+        return Err(format!("duracion_segundos debe estar entre 1 y {}", stress::MAX_DURACION));
+    }
+    stress::argumentos_stress(&solicitud.categoria, solicitud.intensidad, solicitud.duracion_segundos)
+        .ok_or_else(|| format!("Categoría '{}' no válida, use una de {:?}", solicitud.categoria, stress::CATEGORIAS))
+}
+
+/// `POST /api/stress`, crea contenedores de stress al momento
+pub async fn post_stress(data: web::Data<AppState>, solicitud: web::Json<SolicitudStress>) -> impl Responder {
+    let solicitud = solicitud.into_inner();
+
+    let args = match validar_stress(&solicitud) {
+        Ok(args) => args,
+        Err(e) => return error_json(StatusCode::BAD_REQUEST, e),
+    };
+
+    let mut ids = Vec::new();
+    let mut logs = Vec::new();
+    let mut error = None;
+
+    for _ in 0..solicitud.cantidad {
+        match stress::lanzar_contenedor(&data.docker, &solicitud.categoria, &args).await {
+            Ok((id, name)) => {
+                data.protegidos.proteger(&id, solicitud.duracion_segundos);
+                ids.push(id);
+                logs.push(ContainerLog {
+                    timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+                    category: solicitud.categoria.clone(),
+                    name,
+                    action: "creado".to_string(),
+                });
+            }
+            Err(e) => {
+                error = Some(e);
+                break;
+            }
+        }
+    }
+
+    if !logs.is_empty() {
+        data.monitor.registrar_eventos(&logs);
+        if let Ok(Err(e)) = web::block(move || enviar_container_logs(&logs)).await {
+            log::error!("No se pudo escribir el log del contenedor: {}", e);
+        }
+    }
+
+    match error {
+        None => HttpResponse::Created().json(json!({ "ids": ids, "args": args })),
+        // Si falló a medias se devuelven también los que sí se crearon
+        Some(e) => {
+            let (status, mensaje) = status_docker(e);
+            HttpResponse::build(status).json(json!({ "error": mensaje, "ids": ids }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContainerInfo;
+
+    fn contenedor(pid: u32, categoria: &str, cpu_percent: u8, memory_rss: u64) -> ContenedorVivo {
+        ContenedorVivo {
+            info: ContainerInfo {
+                pid,
+                name: format!("stress-{}", pid),
+                cmdline: String::new(),
+                memory_rss,
+                memory_percent: 0,
+                virtual_memory: 0,
+                cpu_percent,
+                disk_usage: 0,
+                io_read_bytes: 0,
+                io_write_bytes: 0,
+            },
+            categoria: Some(categoria.to_string()),
+        }
+    }
+
+    fn contenedores() -> Vec<ContenedorVivo> {
+        vec![contenedor(1, "cpu", 90, 100), contenedor(2, "ram", 5, 4096), contenedor(3, "ram", 20, 2048), contenedor(4, "io", 1, 10)]
+    }
+
+    fn filtro(query: &str) -> FiltroContenedores {
+        web::Query::<FiltroContenedores>::from_query(query).unwrap().into_inner()
+    }
+
+    fn pids(pagina: &PaginaContenedores) -> Vec<u32> {
+        pagina.containers.iter().map(|c| c.info.pid).collect()
+    }
+
+    #[test]
+    fn filtra_por_categoria_cpu_y_rss() {
+        assert_eq!(pids(&paginar(contenedores(), &filtro("categoria=ram")).unwrap()), vec![2, 3]);
+        assert_eq!(pids(&paginar(contenedores(), &filtro("min_cpu=20")).unwrap()), vec![1, 3]);
+        assert_eq!(pids(&paginar(contenedores(), &filtro("categoria=ram&min_rss=3000")).unwrap()), vec![2]);
+    }
+
+    #[test]
+    fn ordena_ascendente_y_descendente() {
+        assert_eq!(pids(&paginar(contenedores(), &filtro("orden=memory_rss")).unwrap()), vec![4, 1, 3, 2]);
+        assert_eq!(pids(&paginar(contenedores(), &filtro("orden=-cpu_percent")).unwrap()), vec![1, 3, 2, 4]);
+    }
+
+    #[test]
+    fn campo_de_orden_desconocido() {
+        let error = paginar(contenedores(), &filtro("orden=-color")).err().unwrap();
+        assert_eq!(error, "No se puede ordenar por 'color'");
+    }
+
+    #[test]
+    fn pagina_y_por_pagina() {
+        let pagina = paginar(contenedores(), &filtro("orden=pid&pagina=2&por_pagina=3")).unwrap();
+        assert_eq!((pagina.total, pagina.pagina, pagina.por_pagina), (4, 2, 3));
+        assert_eq!(pids(&pagina), vec![4]);
+
+        // Fuera de rango se ajusta en lugar de fallar
+        let pagina = paginar(contenedores(), &filtro("pagina=0&por_pagina=0")).unwrap();
+        assert_eq!((pagina.pagina, pagina.por_pagina, pagina.containers.len()), (1, 1, 1));
+        let pagina = paginar(contenedores(), &filtro(&format!("pagina={}", usize::MAX))).unwrap();
+        assert!(pagina.containers.is_empty());
+    }
+
+    #[test]
+    fn solicitud_de_stress() {
+        let solicitud = |json: &str| serde_json::from_str::<SolicitudStress>(json).unwrap();
+        let args = validar_stress(&solicitud(r#"{"categoria":"cpu"}"#)).unwrap();
+        assert_eq!(args.last().unwrap(), "30s");
+
+        let casos = [
+            (r#"{"categoria":"cpu","cantidad":0}"#, "cantidad"),
+            (r#"{"categoria":"cpu","intensidad":99}"#, "intensidad"),
+            (r#"{"categoria":"cpu","duracion_segundos":0}"#, "duracion_segundos"),
+            (r#"{"categoria":"gpu"}"#, "Categoría 'gpu'"),
+        ];
+        for (json, esperado) in casos {
+            let error = validar_stress(&solicitud(json)).unwrap_err();
+            assert!(error.contains(esperado), "{}: {}", json, error);
+        }
+    }
+
+    #[test]
+    fn accion_desconocida_es_400() {
+        assert_eq!(accion_desconocida("reboot").status(), StatusCode::BAD_REQUEST);
+    }
+}
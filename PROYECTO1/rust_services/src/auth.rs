@@ -0,0 +1,175 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::AppState;
+
+/// Alcance de un token, `admin` también puede hacer todo lo de `read`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rol {
+    Read,
+    Admin,
+}
+
+impl Rol {
+    fn nombre(self) -> &'static str {
+        match self {
+            Rol::Read => "read",
+            Rol::Admin => "admin",
+        }
+    }
+}
+
+/// Un token del archivo de configuración, solo se guarda su SHA-256 en hexadecimal
+/// (`echo -n 'mi-token' | sha256sum`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenConfig {
+    pub nombre: String,
+    pub sha256: String,
+    pub rol: Rol,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigAuth {
+    /// Sin tokens la API queda abierta como antes
+    pub tokens: Vec<TokenConfig>,
+}
+
+impl ConfigAuth {
+    pub fn habilitada(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    fn buscar(&self, token: &str) -> Option<&TokenConfig> {
+        let hash = sha256_hex(token);
+        self.tokens.iter().find(|t| t.sha256.eq_ignore_ascii_case(&hash))
+    }
+}
+
+pub fn sha256_hex(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Rol que pide cada ruta, `None` es pública.
+/// Las lecturas (métricas, logs, streams) piden `read` y todo lo que cambia algo
+/// (control de contenedores, stress, gráficas, cron, configuración) pide `admin`.
+fn rol_requerido(method: &Method, path: &str) -> Option<Rol> {
+    let publica = path == "/"
+        || path == "/healthz"
+        || path == "/readyz"
+        || (path.starts_with("/dashboard") && path != "/dashboard/estado" && path != "/dashboard/eventos");
+
+    if method == Method::GET || method == Method::HEAD {
+        if publica {
+            None
+        } else {
+            Some(Rol::Read)
+        }
+    } else {
+        Some(Rol::Admin)
+    }
+}
+
+/// Rol que pide una petición, decidido sobre la ruta ya decodificada con la que
+/// enruta actix (`/dashboard/%65stado` es `/dashboard/estado`), no sobre la cruda
+fn rol_de(req: &ServiceRequest) -> Option<Rol> {
+    rol_requerido(req.method(), req.match_info().as_str())
+}
+
+/// Token de `Authorization: Bearer`, de `X-API-Token` o del parámetro `token`
+/// (EventSource y WebSocket del navegador no pueden mandar headers)
+pub fn token_de(req: &ServiceRequest) -> Option<String> {
+    let headers = req.headers();
+    if let Some(valor) = headers.get("Authorization").and_then(|v| v.to_str().ok())
+        && let Some(token) = valor.strip_prefix("Bearer ")
+    {
+        return Some(token.trim().to_string());
+    }
+    if let Some(valor) = headers.get("X-API-Token").and_then(|v| v.to_str().ok()) {
+        return Some(valor.trim().to_string());
+    }
+
+    web::Query::<Vec<(String, String)>>::from_query(req.query_string())
+        .ok()
+        .and_then(|q| q.into_inner().into_iter().find(|(k, _)| k == "token").map(|(_, v)| v))
+}
+
+/// Middleware de autenticación, va dentro del que registra las peticiones
+/// para que los 401 y 403 también queden en `requests.log`
+pub async fn verificar(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let auth = match req.app_data::<web::Data<AppState>>() {
+        Some(data) if data.auth.habilitada() => data.auth.clone(),
+        _ => return next.call(req).await.map(ServiceResponse::map_into_left_body),
+    };
+
+    let requerido = match rol_de(&req) {
+        Some(rol) => rol,
+        None => return next.call(req).await.map(ServiceResponse::map_into_left_body),
+    };
+
+    let rechazo = match token_de(&req).as_deref().map(|token| auth.buscar(token)) {
+        None => Some((StatusCode::UNAUTHORIZED, "Falta el token de acceso".to_string())),
+        Some(None) => Some((StatusCode::UNAUTHORIZED, "Token de acceso no válido".to_string())),
+        Some(Some(token)) if token.rol < requerido => {
+            Some((StatusCode::FORBIDDEN, format!("El token '{}' necesita el rol {}", token.nombre, requerido.nombre())))
+        }
+        Some(Some(_)) => None,
+    };
+
+    match rechazo {
+        None => next.call(req).await.map(ServiceResponse::map_into_left_body),
+        Some((status, mensaje)) => {
+            log::warn!("{} {} rechazada: {}", req.method(), req.path(), mensaje);
+            let mut respuesta = HttpResponse::build(status);
+            if status == StatusCode::UNAUTHORIZED {
+                respuesta.insert_header(("WWW-Authenticate", "Bearer"));
+            }
+            let respuesta = respuesta.json(json!({ "error": mensaje }));
+            Ok(req.into_response(respuesta).map_into_right_body())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn rutas_publicas_y_protegidas() {
+        assert_eq!(rol_requerido(&Method::GET, "/healthz"), None);
+        assert_eq!(rol_requerido(&Method::GET, "/dashboard/index.html"), None);
+        assert_eq!(rol_requerido(&Method::GET, "/dashboard/estado"), Some(Rol::Read));
+        assert_eq!(rol_requerido(&Method::GET, "/dashboard/eventos"), Some(Rol::Read));
+        assert_eq!(rol_requerido(&Method::GET, "/api/containers"), Some(Rol::Read));
+        assert_eq!(rol_requerido(&Method::POST, "/healthz"), Some(Rol::Admin));
+    }
+
+    #[test]
+    fn ruta_codificada_no_se_salta_la_autenticacion() {
+        for uri in ["/dashboard/%65stado", "/dashboard/%65ventos", "/dashboard/estad%6F?token=x"] {
+            let req = TestRequest::with_uri(uri).to_srv_request();
+            assert_eq!(rol_de(&req), Some(Rol::Read), "{}", uri);
+        }
+        let req = TestRequest::with_uri("/%68ealthz").to_srv_request();
+        assert_eq!(rol_de(&req), None);
+    }
+
+    #[test]
+    fn token_de_headers_y_query() {
+        let req = TestRequest::default().insert_header(("Authorization", "Bearer  abc ")).to_srv_request();
+        assert_eq!(token_de(&req).as_deref(), Some("abc"));
+        let req = TestRequest::default().insert_header(("X-API-Token", "xyz")).to_srv_request();
+        assert_eq!(token_de(&req).as_deref(), Some("xyz"));
+        let req = TestRequest::with_uri("/api/eventos?token=q1").to_srv_request();
+        assert_eq!(token_de(&req).as_deref(), Some("q1"));
+        assert_eq!(token_de(&TestRequest::default().to_srv_request()), None);
+    }
+}
@@ -0,0 +1,133 @@
+//! Cierre ordenado con SIGINT o SIGTERM. Cada paso del cierre tiene su tiempo límite,
+//! si uno se cuelga se sigue con el siguiente y el proceso termina de todas formas.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{oneshot, Notify};
+
+/// Lo que espera el monitor para terminar su ciclo
+pub const ESPERA_MONITOR: Duration = Duration::from_secs(15);
+/// Peticiones en curso, los streams de `/stream` y `/dashboard/eventos` se cortan al vencer
+pub const ESPERA_HTTP: Duration = Duration::from_secs(10);
+pub const ESPERA_LOGS: Duration = Duration::from_secs(5);
+/// El reporte y las gráficas, plotters puede tardar con sesiones largas
+pub const ESPERA_REPORTE: Duration = Duration::from_secs(30);
+pub const ESPERA_DOCKER: Duration = Duration::from_secs(15);
+pub const ESPERA_CRONTAB: Duration = Duration::from_secs(5);
+
+/// Avisa a las tareas de fondo que el servicio se está cerrando
+#[derive(Debug, Default)]
+pub struct Cierre {
+    iniciado: AtomicBool,
+    aviso: Notify,
+}
+
+impl Cierre {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn iniciar(&self) {
+        self.iniciado.store(true, Ordering::SeqCst);
+        self.aviso.notify_waiters();
+    }
+
+    pub fn iniciado(&self) -> bool {
+        self.iniciado.load(Ordering::SeqCst)
+    }
+
+    /// Termina cuando se inicia el cierre, de inmediato si ya se había iniciado
+    pub async fn esperar(&self) {
+        let aviso = self.aviso.notified();
+        tokio::pin!(aviso);
+        // Se registra antes de revisar la bandera para no perder un aviso que llegue en medio
+        aviso.as_mut().enable();
+        if self.iniciado() {
+            return;
+        }
+        aviso.await;
+    }
+}
+
+/// Registra SIGINT y SIGTERM una sola vez, al iniciar, y las cuenta desde ahí. La primera
+/// señal se entrega al receptor devuelto para empezar el cierre; con la segunda se sale
+/// sin esperar a los pasos que faltan.
+pub fn escuchar_senales() -> std::io::Result<oneshot::Receiver<&'static str>> {
+    let mut sigint = signal(SignalKind::interrupt())?;
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let (primera, receptor) = oneshot::channel();
+
+    tokio::spawn(async move {
+        let mut primera = Some(primera);
+        loop {
+            let senal = tokio::select! {
+                _ = sigint.recv() => "SIGINT",
+                _ = sigterm.recv() => "SIGTERM",
+            };
+            match primera.take() {
+                // Si ya nadie espera la señal igual cuenta como la primera
+                Some(primera) => {
+                    let _ = primera.send(senal);
+                }
+                None => {
+                    eprintln!("{} recibida otra vez, saliendo sin terminar el cierre", senal);
+                    std::process::exit(130);
+                }
+            }
+        }
+    });
+    Ok(receptor)
+}
+
+/// Corre un paso del cierre con su tiempo límite e imprime cómo le fue.
+/// Devuelve `false` si falló o no terminó a tiempo.
+pub async fn paso<F>(nombre: &str, limite: Duration, paso: F) -> bool
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let inicio = Instant::now();
+    match tokio::time::timeout(limite, paso).await {
+        Ok(Ok(())) => {
+            println!("✔ {} ({} ms)", nombre, inicio.elapsed().as_millis());
+            true
+        }
+        Ok(Err(e)) => {
+            log::error!("Cierre: {} falló: {}", nombre, e);
+            println!("✘ {}: {}", nombre, e);
+            false
+        }
+        Err(_) => {
+            log::error!("Cierre: {} no terminó en {} s", nombre, limite.as_secs());
+            println!("✘ {}: no terminó en {} s", nombre, limite.as_secs());
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn paso_que_termina_bien() {
+        assert!(paso("bien", Duration::from_secs(1), async { Ok(()) }).await);
+    }
+
+    #[tokio::test]
+    async fn paso_con_error_no_cuenta_como_terminado() {
+        assert!(!paso("error", Duration::from_secs(1), async { Err("falló".to_string()) }).await);
+    }
+
+    #[tokio::test]
+    async fn paso_que_se_pasa_del_limite_se_abandona() {
+        let inicio = Instant::now();
+        let colgado = paso("colgado", Duration::from_millis(50), async {
+            std::future::pending::<()>().await;
+            Ok(())
+        });
+        assert!(!colgado.await);
+        assert!(inicio.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,529 @@
+//! Configuración del servicio en capas: los valores por defecto, el archivo TOML,
+//! las variables de entorno `MONITOR_SECCION__CLAVE` y las opciones de la línea de comandos,
+//! cada una manda sobre las anteriores. Se revisa toda junta al arrancar.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use toml::{Table, Value};
+
+use crate::alertas::ConfigAlertas;
+use crate::auth::ConfigAuth;
+use crate::escenarios::ConfigEscenarios;
+use crate::limite::ConfigLimites;
+use crate::programador::{self, ConfigProgramador};
+use crate::stress::ConfigStress;
+use crate::tls::ConfigTls;
+
+// Archivo de configuración por defecto, se puede cambiar con MONITOR_CONFIG o --config
+const CONFIG_PATH: &str = "config.toml";
+// MONITOR_SERVIDOR__PUERTO=5001 es servidor.puerto
+const PREFIJO_ENTORNO: &str = "MONITOR_";
+const SEPARADOR_ENTORNO: &str = "__";
+
+static ACTUAL: OnceLock<Config> = OnceLock::new();
+
+/// Sección `[rutas]`. `graficas` es donde el monitor escribe `cpu.json`, `ram.json`,
+/// `contenedores.json` y `metricas_contenedores.ndjson`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigRutas {
+    /// Archivo del módulo de kernel en /proc
+    pub sysinfo: String,
+    pub graficas: String,
+    /// Donde se escriben los `.svg` y `.png` de plotters
+    pub graficas_salida: String,
+    pub cron_script: String,
+    pub dashboard: String,
+}
+
+impl Default for ConfigRutas {
+    fn default() -> Self {
+        ConfigRutas {
+            sysinfo: "/proc/sysinfo_202201724".to_string(),
+            graficas: concat!(env!("CARGO_MANIFEST_DIR"), "/graficas").to_string(),
+            graficas_salida: concat!(env!("CARGO_MANIFEST_DIR"), "/graficas/salida").to_string(),
+            cron_script: concat!(env!("CARGO_MANIFEST_DIR"), "/../sscripts/script.sh").to_string(),
+            dashboard: concat!(env!("CARGO_MANIFEST_DIR"), "/static/dashboard").to_string(),
+        }
+    }
+}
+
+impl ConfigRutas {
+    pub fn cpu_json(&self) -> String {
+        self.en_graficas("cpu.json")
+    }
+
+    pub fn ram_json(&self) -> String {
+        self.en_graficas("ram.json")
+    }
+
+    pub fn conteo_json(&self) -> String {
+        self.en_graficas("contenedores.json")
+    }
+
+    pub fn metricas_contenedores(&self) -> String {
+        self.en_graficas("metricas_contenedores.ndjson")
+    }
+
+    fn en_graficas(&self, archivo: &str) -> String {
+        Path::new(&self.graficas).join(archivo).to_string_lossy().into_owned()
+    }
+}
+
+/// Sección `[servidor]`, también aplica a HTTPS cuando hay `[tls]`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigServidor {
+    pub direccion: String,
+    pub puerto: u16,
+}
+
+impl Default for ConfigServidor {
+    fn default() -> Self {
+        ConfigServidor { direccion: "0.0.0.0".to_string(), puerto: 5000 }
+    }
+}
+
+/// Sección `[monitor]`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigMonitor {
+    /// Segundos entre cada ciclo de `serve` y `monitor`
+    pub intervalo_segundos: u64,
+}
+
+impl Default for ConfigMonitor {
+    fn default() -> Self {
+        ConfigMonitor { intervalo_segundos: 10 }
+    }
+}
+
+/// Sección `[logger]`: el contenedor que guarda los logs de peticiones y de contenedores.
+/// `volumen` se monta en `/logs` del contenedor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigLogger {
+    pub nombre: String,
+    pub imagen: String,
+    pub volumen: String,
+}
+
+impl Default for ConfigLogger {
+    fn default() -> Self {
+        ConfigLogger {
+            nombre: "http_request_logger".to_string(),
+            imagen: "alpine".to_string(),
+            volumen: "/tmp/http_logs".to_string(),
+        }
+    }
+}
+
+impl ConfigLogger {
+    pub fn requests_log(&self) -> String {
+        Path::new(&self.volumen).join("requests.log").to_string_lossy().into_owned()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub servidor: ConfigServidor,
+    pub monitor: ConfigMonitor,
+    pub rutas: ConfigRutas,
+    pub logger: ConfigLogger,
+    pub stress: ConfigStress,
+    pub alertas: ConfigAlertas,
+    pub auth: ConfigAuth,
+    pub tls: ConfigTls,
+    pub limites: ConfigLimites,
+    pub programador: ConfigProgramador,
+    pub escenarios: ConfigEscenarios,
+}
+
+/// Un valor que llega de la línea de comandos, `origen` es la opción que lo puso
+#[derive(Debug, Clone)]
+pub struct Sobrescritura {
+    pub clave: String,
+    pub valor: Value,
+    pub origen: String,
+}
+
+impl Sobrescritura {
+    pub fn new(clave: &str, valor: impl Into<Value>, origen: &str) -> Self {
+        Sobrescritura { clave: clave.to_string(), valor: valor.into(), origen: origen.to_string() }
+    }
+
+    /// `--set servidor.puerto=5001`, el valor se lee como TOML y si no se puede como texto
+    pub fn desde_set(texto: &str) -> Result<Self, String> {
+        match texto.split_once('=') {
+            Some((clave, valor)) if !clave.trim().is_empty() => Ok(Sobrescritura {
+                clave: clave.trim().to_string(),
+                valor: leer_valor(valor.trim()),
+                origen: "--set".to_string(),
+            }),
+            _ => Err(format!("--set espera clave=valor, se recibió '{}'", texto)),
+        }
+    }
+}
+
+/// De dónde salió cada valor que no es el de por defecto, por clave (`servidor.puerto`)
+pub type Origenes = BTreeMap<String, String>;
+
+/// Junta las capas y revisa el resultado. `archivo` es el de `--config`, sin él se usa
+/// MONITOR_CONFIG o `config.toml`. Un archivo pedido que no existe es un error,
+/// el de por defecto solo se avisa.
+pub fn cargar(archivo: Option<&Path>, sobrescrituras: &[Sobrescritura]) -> Result<(Config, Origenes), String> {
+    cargar_con_entorno(archivo, std::env::vars().collect(), sobrescrituras)
+}
+
+// Las variables de entorno se reciben aparte para probar las capas sin tocar el proceso
+fn cargar_con_entorno(
+    archivo: Option<&Path>,
+    mut entorno: Vec<(String, String)>,
+    sobrescrituras: &[Sobrescritura],
+) -> Result<(Config, Origenes), String> {
+    let mut raiz = match Value::try_from(Config::default()) {
+        Ok(Value::Table(raiz)) => raiz,
+        _ => return Err("No se pudo armar la configuración por defecto".to_string()),
+    };
+    let mut origenes = Origenes::new();
+
+    // Archivo
+    let monitor_config = entorno.iter().find(|(nombre, _)| nombre == "MONITOR_CONFIG").map(|(_, ruta)| ruta);
+    let (ruta, pedida) = match (archivo, monitor_config) {
+        (Some(ruta), _) => (ruta.to_path_buf(), true),
+        (None, Some(ruta)) => (PathBuf::from(ruta), true),
+        (None, None) => (PathBuf::from(CONFIG_PATH), false),
+    };
+    if ruta.exists() {
+        let data = fs::read_to_string(&ruta).map_err(|e| format!("No se pudo leer {}: {}", ruta.display(), e))?;
+        let tabla: Table = toml::from_str(&data).map_err(|e| format!("Error en {}: {}", ruta.display(), e))?;
+        mezclar(&mut raiz, tabla, "", &ruta.display().to_string(), &mut origenes);
+    } else if pedida {
+        return Err(format!("No existe el archivo de configuración {}", ruta.display()));
+    } else {
+        log::warn!("No se encontró {}, se usa la configuración por defecto", ruta.display());
+    }
+
+    // Variables de entorno
+    entorno.sort();
+    for (nombre, valor) in entorno {
+        let clave = match nombre.strip_prefix(PREFIJO_ENTORNO) {
+            Some(resto) if resto.contains(SEPARADOR_ENTORNO) => resto.to_lowercase().replace(SEPARADOR_ENTORNO, "."),
+            // GRAFICAS_DIR es de antes de que existiera [rutas]
+            _ if nombre == "GRAFICAS_DIR" => "rutas.graficas_salida".to_string(),
+            _ => continue,
+        };
+        fijar(&mut raiz, &clave, leer_valor(&valor), &nombre, &mut origenes)?;
+    }
+
+    // Línea de comandos
+    for sobrescritura in sobrescrituras {
+        fijar(&mut raiz, &sobrescritura.clave, sobrescritura.valor.clone(), &sobrescritura.origen, &mut origenes)?;
+    }
+
+    let config: Config = serde_path_to_error::deserialize(Value::Table(raiz.clone())).map_err(|e| {
+        let clave = e.path().to_string();
+        match origenes.get(&clave) {
+            Some(origen) => format!("Configuración inválida:\n  {}: {} (en {})", clave, e.inner().message(), origen),
+            None => format!("Configuración inválida:\n  {}: {}", clave, e.inner().message()),
+        }
+    })?;
+
+    let desconocidas = claves_desconocidas(&raiz, &config);
+    if !desconocidas.is_empty() {
+        let lista: Vec<String> = desconocidas
+            .iter()
+            .map(|clave| format!("  {} (en {})", clave, origenes.get(clave).map(String::as_str).unwrap_or("?")))
+            .collect();
+        return Err(format!("Claves de configuración desconocidas:\n{}", lista.join("\n")));
+    }
+
+    let errores = config.validar();
+    if !errores.is_empty() {
+        let lista: Vec<String> = errores
+            .iter()
+            .map(|(clave, error)| match origenes.get(clave) {
+                Some(origen) => format!("  {}: {} (en {})", clave, error, origen),
+                None => format!("  {}: {}", clave, error),
+            })
+            .collect();
+        return Err(format!("Configuración inválida:\n{}", lista.join("\n")));
+    }
+    Ok((config, origenes))
+}
+
+/// Deja la configuración para todo el proceso, se llama una vez al arrancar
+pub fn instalar(config: Config) {
+    if ACTUAL.set(config).is_err() {
+        log::warn!("La configuración ya estaba instalada, se ignora la nueva");
+    }
+}
+
+/// La configuración instalada, o la de por defecto si no se instaló ninguna
+pub fn actual() -> &'static Config {
+    ACTUAL.get_or_init(Config::default)
+}
+
+/// La configuración efectiva como TOML, con un encabezado que dice de dónde salió cada valor
+/// distinto al de por defecto
+pub fn imprimir(config: &Config, origenes: &Origenes) -> Result<String, String> {
+    let (defecto, efectiva) = match (Value::try_from(Config::default()), Value::try_from(config)) {
+        (Ok(defecto), Ok(efectiva)) => (defecto, efectiva),
+        (Err(e), _) | (_, Err(e)) => return Err(e.to_string()),
+    };
+    let cambiados: Vec<(&String, &String)> =
+        origenes.iter().filter(|(clave, _)| buscar(&defecto, clave) != buscar(&efectiva, clave)).collect();
+
+    let mut salida = String::from("# Configuración efectiva\n");
+    if cambiados.is_empty() {
+        salida.push_str("# Todos los valores son los de por defecto\n");
+    }
+    for (clave, origen) in cambiados {
+        salida.push_str(&format!("# {} <- {}\n", clave, origen));
+    }
+    salida.push('\n');
+    salida.push_str(&toml::to_string_pretty(config).map_err(|e| e.to_string())?);
+    Ok(salida)
+}
+
+impl Config {
+    /// Todos los errores juntos, cada uno con la clave que lo causa
+    fn validar(&self) -> Vec<(String, String)> {
+        let mut errores = Vec::new();
+        let mut error = |clave: &str, mensaje: String| errores.push((clave.to_string(), mensaje));
+
+        if self.servidor.direccion.parse::<IpAddr>().is_err() {
+            error("servidor.direccion", format!("'{}' no es una dirección IP", self.servidor.direccion));
+        }
+        if self.servidor.puerto == 0 {
+            error("servidor.puerto", "debe estar entre 1 y 65535".to_string());
+        }
+        if self.monitor.intervalo_segundos == 0 || self.monitor.intervalo_segundos > 3600 {
+            error("monitor.intervalo_segundos", "debe estar entre 1 y 3600".to_string());
+        }
+
+        let rutas = [
+            ("rutas.sysinfo", &self.rutas.sysinfo),
+            ("rutas.graficas", &self.rutas.graficas),
+            ("rutas.graficas_salida", &self.rutas.graficas_salida),
+            ("rutas.cron_script", &self.rutas.cron_script),
+            ("rutas.dashboard", &self.rutas.dashboard),
+        ];
+        for (clave, ruta) in rutas {
+            if ruta.trim().is_empty() {
+                error(clave, "no puede estar vacía".to_string());
+            }
+        }
+        // El crontab corre el script desde cualquier directorio
+        if !Path::new(&self.rutas.cron_script).is_absolute() {
+            error("rutas.cron_script", format!("'{}' debe ser una ruta absoluta", self.rutas.cron_script));
+        }
+
+        // Docker solo acepta [a-zA-Z0-9][a-zA-Z0-9_.-]* como nombre de contenedor
+        let nombre = &self.logger.nombre;
+        let nombre_valido = nombre.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && nombre.chars().all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c));
+        if !nombre_valido {
+            error("logger.nombre", format!("'{}' no es un nombre de contenedor válido", nombre));
+        }
+        if self.logger.imagen.trim().is_empty() {
+            error("logger.imagen", "no puede estar vacía".to_string());
+        }
+        if !Path::new(&self.logger.volumen).is_absolute() {
+            error("logger.volumen", format!("'{}' debe ser una ruta absoluta", self.logger.volumen));
+        }
+
+        if let Err(e) = self.alertas.validar() {
+            error("alertas", e);
+        }
+        if let Err(e) = self.stress.validar() {
+            error("stress", e);
+        }
+        if let Err(e) = self.limites.validar() {
+            error("limites", e);
+        }
+        if self.tls.cert.is_some() != self.tls.key.is_some() {
+            error("tls", "tls.cert y tls.key van juntos".to_string());
+        }
+        for trabajo in &self.programador.trabajos {
+            if let Err(e) = programador::validar(trabajo) {
+                error("programador.trabajos", format!("trabajo '{}': {}", trabajo.nombre, e));
+            }
+        }
+        errores
+    }
+}
+
+/// Un valor de una variable de entorno o de `--set`: `5001`, `true` o `["a", "b"]` se leen
+/// como TOML y lo que no se puede leer queda como texto, así `/tmp/logs` no necesita comillas
+fn leer_valor(texto: &str) -> Value {
+    toml::from_str::<Table>(&format!("v = {}", texto))
+        .ok()
+        .and_then(|mut tabla| tabla.remove("v"))
+        .unwrap_or_else(|| Value::String(texto.to_string()))
+}
+
+/// Mezcla `capa` sobre `raiz`: las tablas se juntan y lo demás se reemplaza
+fn mezclar(raiz: &mut Table, capa: Table, prefijo: &str, origen: &str, origenes: &mut Origenes) {
+    for (clave, valor) in capa {
+        let ruta = if prefijo.is_empty() { clave.clone() } else { format!("{}.{}", prefijo, clave) };
+        match (raiz.get_mut(&clave), valor) {
+            (Some(Value::Table(destino)), Value::Table(tabla)) => mezclar(destino, tabla, &ruta, origen, origenes),
+            (_, Value::Table(tabla)) => {
+                let mut nueva = Table::new();
+                mezclar(&mut nueva, tabla, &ruta, origen, origenes);
+                raiz.insert(clave, Value::Table(nueva));
+            }
+            (_, valor) => {
+                origenes.insert(ruta, origen.to_string());
+                raiz.insert(clave, valor);
+            }
+        }
+    }
+}
+
+/// Pone un valor en `seccion.clave`, creando las tablas que falten
+fn fijar(raiz: &mut Table, clave: &str, valor: Value, origen: &str, origenes: &mut Origenes) -> Result<(), String> {
+    let partes: Vec<&str> = clave.split('.').collect();
+    if partes.iter().any(|parte| parte.is_empty()) {
+        return Err(format!("Clave de configuración '{}' no válida (en {})", clave, origen));
+    }
+    let Some((ultima, secciones)) = partes.split_last() else {
+        return Err(format!("Clave de configuración vacía (en {})", origen));
+    };
+
+    let mut tabla = raiz;
+    for (i, seccion) in secciones.iter().enumerate() {
+        tabla = match tabla.entry(seccion.to_string()).or_insert_with(|| Value::Table(Table::new())) {
+            Value::Table(tabla) => tabla,
+            _ => return Err(format!("'{}' no es una sección, no se puede usar {} (en {})", partes[..=i].join("."), clave, origen)),
+        };
+    }
+    let mut capa = Table::new();
+    capa.insert(ultima.to_string(), valor);
+    mezclar(tabla, capa, &secciones.join("."), origen, origenes);
+    Ok(())
+}
+
+fn buscar<'a>(valor: &'a Value, clave: &str) -> Option<&'a Value> {
+    clave.split('.').try_fold(valor, |valor, parte| valor.get(parte))
+}
+
+/// Las claves hoja que se pusieron pero que `Config` no tiene, por ejemplo `servidor.puerta`.
+/// Se compara con la configuración vuelta a serializar, las listas cuentan como hojas.
+fn claves_desconocidas(raiz: &Table, config: &Config) -> Vec<String> {
+    let conocidas = match Value::try_from(config) {
+        Ok(Value::Table(tabla)) => hojas(&tabla, ""),
+        _ => BTreeSet::new(),
+    };
+    hojas(raiz, "").into_iter().filter(|clave| !conocidas.contains(clave)).collect()
+}
+
+fn hojas(tabla: &Table, prefijo: &str) -> BTreeSet<String> {
+    let mut claves = BTreeSet::new();
+    for (clave, valor) in tabla {
+        let ruta = if prefijo.is_empty() { clave.clone() } else { format!("{}.{}", prefijo, clave) };
+        match valor {
+            Value::Table(tabla) => claves.extend(hojas(tabla, &ruta)),
+            _ => {
+                claves.insert(ruta);
+            }
+        }
+    }
+    claves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entorno(variables: &[(&str, &str)]) -> Vec<(String, String)> {
+        variables.iter().map(|(nombre, valor)| (nombre.to_string(), valor.to_string())).collect()
+    }
+
+    fn archivo(dir: &Path, contenido: &str) -> PathBuf {
+        let ruta = dir.join("config.toml");
+        fs::write(&ruta, contenido).unwrap();
+        ruta
+    }
+
+    #[test]
+    fn cada_capa_manda_sobre_la_anterior() {
+        let dir = tempfile::tempdir().unwrap();
+        let ruta = archivo(dir.path(), "[servidor]\npuerto = 6000\ndireccion = \"127.0.0.1\"\n\n[monitor]\nintervalo_segundos = 3\n");
+        let variables = entorno(&[("MONITOR_SERVIDOR__PUERTO", "6001"), ("MONITOR_MONITOR__INTERVALO_SEGUNDOS", "4")]);
+        let sobrescrituras = [Sobrescritura::desde_set("servidor.puerto=6002").unwrap()];
+
+        let (config, origenes) = cargar_con_entorno(Some(&ruta), variables, &sobrescrituras).unwrap();
+        assert_eq!(config.servidor.puerto, 6002);
+        assert_eq!(config.monitor.intervalo_segundos, 4);
+        assert_eq!(config.servidor.direccion, "127.0.0.1");
+        assert_eq!(origenes["servidor.puerto"], "--set");
+        assert_eq!(origenes["monitor.intervalo_segundos"], "MONITOR_MONITOR__INTERVALO_SEGUNDOS");
+        assert_eq!(origenes["servidor.direccion"], ruta.display().to_string());
+    }
+
+    #[test]
+    fn monitor_config_con_archivo_que_no_existe() {
+        let dir = tempfile::tempdir().unwrap();
+        let ruta = dir.path().join("no-existe.toml");
+        let variables = entorno(&[("MONITOR_CONFIG", ruta.to_str().unwrap())]);
+        let error = cargar_con_entorno(None, variables, &[]).unwrap_err();
+        assert_eq!(error, format!("No existe el archivo de configuración {}", ruta.display()));
+    }
+
+    #[test]
+    fn claves_desconocidas_con_su_origen() {
+        let dir = tempfile::tempdir().unwrap();
+        let ruta = archivo(dir.path(), "[servidor]\npuerta = 6000\n");
+        let variables = entorno(&[("MONITOR_MONITOR__INTERVALO", "5")]);
+        let error = cargar_con_entorno(Some(&ruta), variables, &[]).unwrap_err();
+        assert_eq!(
+            error,
+            format!(
+                "Claves de configuración desconocidas:\n  monitor.intervalo (en MONITOR_MONITOR__INTERVALO)\n  servidor.puerta (en {})",
+                ruta.display()
+            )
+        );
+    }
+
+    #[test]
+    fn fijar_sobre_un_valor_que_no_es_seccion() {
+        let mut raiz = Table::new();
+        let mut origenes = Origenes::new();
+        fijar(&mut raiz, "servidor.puerto", Value::Integer(5001), "--set", &mut origenes).unwrap();
+
+        let error = fijar(&mut raiz, "servidor.puerto.numero", Value::Integer(1), "--set", &mut origenes).unwrap_err();
+        assert_eq!(error, "'servidor.puerto' no es una sección, no se puede usar servidor.puerto.numero (en --set)");
+        let error = fijar(&mut raiz, "servidor..puerto", Value::Integer(1), "--set", &mut origenes).unwrap_err();
+        assert!(error.contains("no válida"), "{}", error);
+        assert_eq!(raiz["servidor"]["puerto"], Value::Integer(5001));
+    }
+
+    #[test]
+    fn mezclar_junta_tablas_y_reemplaza_valores() {
+        let mut raiz: Table = toml::from_str("[servidor]\npuerto = 5000\ndireccion = \"0.0.0.0\"\n").unwrap();
+        let capa: Table = toml::from_str("[servidor]\npuerto = 5001\n\n[nueva]\nclave = 1\n").unwrap();
+        let mut origenes = Origenes::new();
+        mezclar(&mut raiz, capa, "", "capa.toml", &mut origenes);
+
+        assert_eq!(raiz["servidor"]["puerto"], Value::Integer(5001));
+        assert_eq!(raiz["servidor"]["direccion"], Value::String("0.0.0.0".to_string()));
+        assert_eq!(raiz["nueva"]["clave"], Value::Integer(1));
+        assert_eq!(origenes.keys().collect::<Vec<_>>(), ["nueva.clave", "servidor.puerto"]);
+    }
+
+    #[test]
+    fn leer_valor_como_toml_o_texto() {
+        assert_eq!(leer_valor("5001"), Value::Integer(5001));
+        assert_eq!(leer_valor("true"), Value::Boolean(true));
+        assert_eq!(leer_valor("[\"a\", \"b\"]"), Value::Array(vec!["a".into(), "b".into()]));
+        assert_eq!(leer_valor("\"entre comillas\""), Value::String("entre comillas".to_string()));
+        assert_eq!(leer_valor("/tmp/logs"), Value::String("/tmp/logs".to_string()));
+    }
+}
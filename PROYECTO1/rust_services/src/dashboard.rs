@@ -0,0 +1,157 @@
+use actix_web::{web, HttpResponse, Responder};
+use futures::StreamExt;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, RwLock};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{AppState, ContainerInfo, ContainerLog, SystemStats};
+
+// Cantidad de eventos de contenedores que se guardan para el dashboard
+const MAX_EVENTOS_RECIENTES: usize = 50;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContenedorVivo {
+    #[serde(flatten)]
+    pub info: ContainerInfo,
+    pub categoria: Option<String>,
+}
+
+/// Lo que el monitor vio en un ciclo, es lo que se empuja al dashboard
+#[derive(Debug, Clone, Serialize)]
+pub struct Instantanea {
+    pub timestamp: u64,
+    pub system: Option<SystemStats>,
+    pub containers: Vec<ContenedorVivo>,
+    pub categorias: HashMap<String, usize>,
+    pub eventos: Vec<ContainerLog>,
+}
+
+pub struct EstadoMonitor {
+    ultima: RwLock<Option<Instantanea>>,
+    eventos_recientes: Mutex<VecDeque<ContainerLog>>,
+    canal: broadcast::Sender<Instantanea>,
+}
+
+impl EstadoMonitor {
+    pub fn new() -> Self {
+        let (canal, _) = broadcast::channel(16);
+        EstadoMonitor {
+            ultima: RwLock::new(None),
+            eventos_recientes: Mutex::new(VecDeque::new()),
+            canal,
+        }
+    }
+
+    /// Agrega eventos de contenedores a la lista de recientes
+    pub fn registrar_eventos(&self, nuevos: &[ContainerLog]) {
+        let mut eventos = self.eventos_recientes.lock().unwrap();
+        for evento in nuevos {
+            if eventos.len() == MAX_EVENTOS_RECIENTES {
+                eventos.pop_front();
+            }
+            eventos.push_back(evento.clone());
+        }
+    }
+
+    /// La última instantánea publicada por el monitor
+    pub fn ultima(&self) -> Option<Instantanea> {
+        self.ultima.read().unwrap().clone()
+    }
+
+    /// Los últimos eventos de contenedores, del más viejo al más nuevo
+    pub fn eventos_recientes(&self) -> Vec<ContainerLog> {
+        self.eventos_recientes.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Guarda la instantánea del ciclo y la envía a los clientes conectados
+    pub fn publicar(&self, instantanea: Instantanea) {
+        self.registrar_eventos(&instantanea.eventos);
+
+        *self.ultima.write().unwrap() = Some(instantanea.clone());
+
+        // Si no hay nadie escuchando send falla, no es un error
+        let _ = self.canal.send(instantanea);
+    }
+}
+
+#[derive(Serialize)]
+struct EstadoDashboard {
+    instantanea: Option<Instantanea>,
+    eventos_recientes: Vec<ContainerLog>,
+}
+
+/// Estado actual para pintar el dashboard al abrirlo
+pub async fn dashboard_estado(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(EstadoDashboard {
+        instantanea: data.monitor.ultima(),
+        eventos_recientes: data.monitor.eventos_recientes(),
+    })
+}
+
+/// Server-Sent Events con una instantánea por cada ciclo del monitor
+pub async fn dashboard_eventos(data: web::Data<AppState>) -> impl Responder {
+    let rx = data.monitor.canal.subscribe();
+
+    // Comentario inicial para que el navegador reciba los headers de una vez
+    let inicio = futures::stream::once(async { Ok::<_, actix_web::Error>(web::Bytes::from_static(b": conectado\n\n")) });
+
+    let stream = futures::stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(instantanea) => {
+                    let json = serde_json::to_string(&instantanea).unwrap_or_default();
+                    let mensaje = web::Bytes::from(format!("data: {}\n\n", json));
+                    return Some((Ok::<_, actix_web::Error>(mensaje), rx));
+                }
+                // El cliente se atrasó, se siguen enviando las más nuevas
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(inicio.chain(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evento(numero: u64) -> ContainerLog {
+        ContainerLog { timestamp: numero, category: "cpu".to_string(), name: format!("stress_cpu_{}", numero), action: "creado".to_string() }
+    }
+
+    fn instantanea(timestamp: u64, eventos: Vec<ContainerLog>) -> Instantanea {
+        Instantanea { timestamp, system: None, containers: Vec::new(), categorias: HashMap::new(), eventos }
+    }
+
+    #[test]
+    fn eventos_recientes_se_quedan_con_los_ultimos() {
+        let estado = EstadoMonitor::new();
+        estado.registrar_eventos(&(0..MAX_EVENTOS_RECIENTES as u64 + 10).map(evento).collect::<Vec<_>>());
+        let eventos = estado.eventos_recientes();
+        assert_eq!(eventos.len(), MAX_EVENTOS_RECIENTES);
+        assert_eq!(eventos.first().unwrap().timestamp, 10);
+        assert_eq!(eventos.last().unwrap().timestamp, MAX_EVENTOS_RECIENTES as u64 + 9);
+    }
+
+    #[test]
+    fn publicar_guarda_y_envia_la_instantanea() {
+        let estado = EstadoMonitor::new();
+        assert!(estado.ultima().is_none());
+        let mut rx = estado.canal.subscribe();
+
+        estado.publicar(instantanea(1, vec![evento(1)]));
+        estado.publicar(instantanea(2, vec![evento(2)]));
+
+        assert_eq!(estado.ultima().unwrap().timestamp, 2);
+        assert_eq!(estado.eventos_recientes().len(), 2);
+        assert_eq!(rx.try_recv().unwrap().timestamp, 1);
+        assert_eq!(rx.try_recv().unwrap().timestamp, 2);
+    }
+}
@@ -0,0 +1,559 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, Responder};
+use bollard::container::RemoveContainerOptions;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, sleep_until, Duration, Instant};
+
+use crate::{enviar_container_logs, stress, AppState, ContainerLog};
+
+// Cada cuánto una fase constante revisa que sigan vivos sus contenedores
+const REVISION_CONSTANTE: Duration = Duration::from_secs(5);
+// Solo se guardan los últimos errores de una ejecución
+const MAX_ERRORES: usize = 20;
+
+/// Un escenario de carga, se lee de `<dir>/<nombre>.toml`, `.yaml` o `.yml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Escenario {
+    #[serde(default)]
+    pub descripcion: String,
+    pub fases: Vec<Fase>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fase {
+    pub nombre: String,
+    pub duracion_segundos: u32,
+    #[serde(flatten)]
+    pub tipo: TipoFase,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "tipo", rename_all = "snake_case")]
+pub enum TipoFase {
+    /// Pasa de `desde` a `hasta` contenedores repartiendo los pasos en la duración de la fase.
+    /// Si `hasta` es menor se van quitando.
+    Rampa {
+        categoria: String,
+        desde: u32,
+        hasta: u32,
+        #[serde(default = "uno")]
+        intensidad: u32,
+    },
+    /// Lanza `cantidad` contenedores de una vez. Por defecto duran toda la fase.
+    Rafaga {
+        categoria: String,
+        cantidad: u32,
+        #[serde(default = "uno")]
+        intensidad: u32,
+        #[serde(default)]
+        duracion_contenedor_segundos: Option<u32>,
+    },
+    /// Mantiene `cantidad` contenedores durante la fase, si alguno se cae se lanza otro
+    Constante {
+        categoria: String,
+        cantidad: u32,
+        #[serde(default = "uno")]
+        intensidad: u32,
+    },
+    /// No lanza nada, sirve de línea base entre fases
+    Reposo,
+}
+
+fn uno() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigEscenarios {
+    /// Directorio con los archivos de escenarios
+    pub dir: String,
+}
+
+impl Default for ConfigEscenarios {
+    fn default() -> Self {
+        ConfigEscenarios { dir: "escenarios".to_string() }
+    }
+}
+
+/// La fase que está corriendo, con ella se etiqueta cada muestra del monitor
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct FaseActiva {
+    pub escenario: String,
+    pub fase: String,
+    /// Distingue dos corridas del mismo escenario en el reporte
+    #[serde(skip)]
+    pub ejecucion: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultadoEjecucion {
+    Corriendo,
+    Terminado,
+    Detenido,
+}
+
+/// Lo que se sirve en `GET /api/escenarios`
+#[derive(Debug, Clone, Serialize)]
+pub struct EjecucionEscenario {
+    pub escenario: String,
+    pub resultado: ResultadoEjecucion,
+    pub inicio: String,
+    pub fin: Option<String>,
+    pub fase: Option<String>,
+    pub fase_indice: usize,
+    pub fases_total: usize,
+    pub inicio_fase: Option<String>,
+    pub contenedores_lanzados: u32,
+    pub errores: Vec<String>,
+    // Contenedores de la corrida que el reconciliador no debe tocar
+    #[serde(skip)]
+    contenedores: HashSet<String>,
+    #[serde(skip)]
+    numero: u64,
+}
+
+#[derive(Default)]
+struct EstadoEscenarios {
+    ejecucion: Option<EjecucionEscenario>,
+    tarea: Option<JoinHandle<()>>,
+    corridas: u64,
+}
+
+/// Corre un escenario a la vez
+pub struct Escenarios {
+    dir: PathBuf,
+    estado: Mutex<EstadoEscenarios>,
+}
+
+fn nombre_valido(nombre: &str) -> bool {
+    !nombre.is_empty() && nombre.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Revisa un escenario antes de correrlo
+pub fn validar(escenario: &Escenario) -> Result<(), String> {
+    if escenario.fases.is_empty() {
+        return Err("El escenario no tiene fases".to_string());
+    }
+
+    let mut nombres = HashSet::new();
+    for fase in &escenario.fases {
+        if fase.nombre.trim().is_empty() {
+            return Err("Todas las fases necesitan un nombre".to_string());
+        }
+        if !nombres.insert(fase.nombre.as_str()) {
+            return Err(format!("Hay dos fases con el nombre '{}'", fase.nombre));
+        }
+        // Los contenedores viven a lo sumo lo que dura la fase, así que aplica el mismo límite
+        if fase.duracion_segundos == 0 || fase.duracion_segundos > stress::MAX_DURACION {
+            return Err(format!("Fase '{}': duracion_segundos debe estar entre 1 y {}", fase.nombre, stress::MAX_DURACION));
+        }
+        validar_tipo(&fase.tipo, fase.duracion_segundos).map_err(|e| format!("Fase '{}': {}", fase.nombre, e))?;
+    }
+    Ok(())
+}
+
+fn validar_tipo(tipo: &TipoFase, duracion: u32) -> Result<(), String> {
+    let (categoria, cantidades, intensidad) = match tipo {
+        TipoFase::Rampa { categoria, desde, hasta, intensidad } => {
+            if desde == hasta {
+                return Err("desde y hasta son iguales, use una fase constante".to_string());
+            }
+            (categoria, vec![("desde", *desde, 0), ("hasta", *hasta, 0)], *intensidad)
+        }
+        TipoFase::Rafaga { categoria, cantidad, intensidad, duracion_contenedor_segundos } => {
+            if let Some(d) = duracion_contenedor_segundos
+                && (*d == 0 || *d > duracion)
+            {
+                return Err(format!("duracion_contenedor_segundos debe estar entre 1 y {}", duracion));
+            }
+            (categoria, vec![("cantidad", *cantidad, 1)], *intensidad)
+        }
+        TipoFase::Constante { categoria, cantidad, intensidad } => (categoria, vec![("cantidad", *cantidad, 1)], *intensidad),
+        TipoFase::Reposo => return Ok(()),
+    };
+
+    if !stress::CATEGORIAS.contains(&categoria.as_str()) {
+        return Err(format!("Categoría '{}' no válida, use una de {:?}", categoria, stress::CATEGORIAS));
+    }
+    for (campo, valor, minimo) in cantidades {
+        if valor < minimo || valor > stress::MAX_CANTIDAD {
+            return Err(format!("{} debe estar entre {} y {}", campo, minimo, stress::MAX_CANTIDAD));
+        }
+    }
+    if intensidad == 0 || intensidad > stress::MAX_INTENSIDAD {
+        return Err(format!("intensidad debe estar entre 1 y {}", stress::MAX_INTENSIDAD));
+    }
+    Ok(())
+}
+
+impl Escenarios {
+    pub fn new(config: &ConfigEscenarios) -> Self {
+        Escenarios {
+            dir: PathBuf::from(&config.dir),
+            estado: Mutex::new(EstadoEscenarios::default()),
+        }
+    }
+
+    /// Nombres de los escenarios que hay en el directorio
+    pub fn disponibles(&self) -> Vec<String> {
+        let mut nombres: Vec<String> = fs::read_dir(&self.dir)
+            .map(|entradas| {
+                entradas
+                    .filter_map(|e| e.ok().map(|e| e.path()))
+                    .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("toml" | "yaml" | "yml")))
+                    .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        nombres.sort();
+        nombres.dedup();
+        nombres
+    }
+
+    /// Lee y valida `<dir>/<nombre>.toml`, `.yaml` o `.yml`
+    pub fn cargar(&self, nombre: &str) -> Result<Escenario, (StatusCode, String)> {
+        if !nombre_valido(nombre) {
+            return Err((StatusCode::BAD_REQUEST, "El nombre solo puede tener letras, números, '-' y '_'".to_string()));
+        }
+        let path = ["toml", "yaml", "yml"]
+            .iter()
+            .map(|ext| self.dir.join(format!("{}.{}", nombre, ext)))
+            .find(|p| p.exists())
+            .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No existe el escenario '{}' en {}", nombre, self.dir.display())))?;
+
+        let data = fs::read_to_string(&path)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("No se pudo leer {}: {}", path.display(), e)))?;
+        let escenario = parsear(&path, &data).map_err(|e| (StatusCode::BAD_REQUEST, format!("Error en {}: {}", path.display(), e)))?;
+        validar(&escenario).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        Ok(escenario)
+    }
+
+    pub fn ejecucion(&self) -> Option<EjecucionEscenario> {
+        self.estado.lock().unwrap().ejecucion.clone()
+    }
+
+    pub fn fase_activa(&self) -> Option<FaseActiva> {
+        let estado = self.estado.lock().unwrap();
+        let ejecucion = estado.ejecucion.as_ref().filter(|e| e.resultado == ResultadoEjecucion::Corriendo)?;
+        Some(FaseActiva {
+            escenario: ejecucion.escenario.clone(),
+            fase: ejecucion.fase.clone()?,
+            ejecucion: ejecucion.numero,
+        })
+    }
+
+    /// Los contenedores del escenario en curso no los quita el reconciliador
+    pub fn protegido(&self, id: &str) -> bool {
+        let estado = self.estado.lock().unwrap();
+        estado.ejecucion.as_ref().is_some_and(|e| e.resultado == ResultadoEjecucion::Corriendo && e.contenedores.contains(id))
+    }
+
+    pub fn iniciar(&self, app: &web::Data<AppState>, nombre: &str, escenario: Escenario) -> Result<EjecucionEscenario, (StatusCode, String)> {
+        let mut estado = self.estado.lock().unwrap();
+        if let Some(actual) = estado.ejecucion.as_ref().filter(|e| e.resultado == ResultadoEjecucion::Corriendo) {
+            return Err((StatusCode::CONFLICT, format!("Ya está corriendo el escenario '{}'", actual.escenario)));
+        }
+
+        estado.corridas += 1;
+        let ejecucion = EjecucionEscenario {
+            escenario: nombre.to_string(),
+            resultado: ResultadoEjecucion::Corriendo,
+            inicio: Utc::now().to_rfc3339(),
+            fin: None,
+            fase: None,
+            fase_indice: 0,
+            fases_total: escenario.fases.len(),
+            inicio_fase: None,
+            contenedores_lanzados: 0,
+            errores: Vec::new(),
+            contenedores: HashSet::new(),
+            numero: estado.corridas,
+        };
+        estado.ejecucion = Some(ejecucion.clone());
+        estado.tarea = Some(tokio::spawn(correr_escenario(app.clone(), escenario)));
+        Ok(ejecucion)
+    }
+
+    /// Cancela la corrida y devuelve sus contenedores para quitarlos
+    pub fn detener(&self) -> Result<(EjecucionEscenario, Vec<String>), (StatusCode, String)> {
+        let mut estado = self.estado.lock().unwrap();
+        if let Some(tarea) = estado.tarea.take() {
+            tarea.abort();
+        }
+        match estado.ejecucion.as_mut().filter(|e| e.resultado == ResultadoEjecucion::Corriendo) {
+            Some(ejecucion) => {
+                ejecucion.resultado = ResultadoEjecucion::Detenido;
+                ejecucion.fin = Some(Utc::now().to_rfc3339());
+                let contenedores = ejecucion.contenedores.drain().collect();
+                Ok((ejecucion.clone(), contenedores))
+            }
+            None => Err((StatusCode::CONFLICT, "No hay un escenario corriendo".to_string())),
+        }
+    }
+
+    fn actualizar(&self, cambio: impl FnOnce(&mut EjecucionEscenario)) {
+        if let Some(ejecucion) = self.estado.lock().unwrap().ejecucion.as_mut() {
+            cambio(ejecucion);
+        }
+    }
+
+    fn registrar_error(&self, error: String) {
+        log::error!("Escenario: {}", error);
+        self.actualizar(|e| {
+            e.errores.push(error);
+            if e.errores.len() > MAX_ERRORES {
+                e.errores.remove(0);
+            }
+        });
+    }
+}
+
+fn parsear(path: &Path, data: &str) -> Result<Escenario, String> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(data).map_err(|e| e.to_string()),
+        _ => serde_yaml::from_str(data).map_err(|e| e.to_string()),
+    }
+}
+
+async fn correr_escenario(app: web::Data<AppState>, escenario: Escenario) {
+    for (indice, fase) in escenario.fases.iter().enumerate() {
+        log::info!("Escenario: empieza la fase '{}' ({} s)", fase.nombre, fase.duracion_segundos);
+        app.escenarios.actualizar(|e| {
+            e.fase = Some(fase.nombre.clone());
+            e.fase_indice = indice;
+            e.inicio_fase = Some(Utc::now().to_rfc3339());
+        });
+
+        let fin = Instant::now() + Duration::from_secs(fase.duracion_segundos as u64);
+        match &fase.tipo {
+            TipoFase::Rampa { categoria, desde, hasta, intensidad } => rampa(&app, fin, categoria, *desde, *hasta, *intensidad).await,
+            TipoFase::Rafaga { categoria, cantidad, intensidad, duracion_contenedor_segundos } => {
+                let segundos = duracion_contenedor_segundos.unwrap_or(fase.duracion_segundos);
+                for _ in 0..*cantidad {
+                    lanzar(&app, categoria, *intensidad, segundos).await;
+                }
+            }
+            TipoFase::Constante { categoria, cantidad, intensidad } => constante(&app, fin, categoria, *cantidad, *intensidad).await,
+            TipoFase::Reposo => {}
+        }
+        sleep_until(fin).await;
+    }
+
+    log::info!("Escenario terminado");
+    app.escenarios.actualizar(|e| {
+        e.resultado = ResultadoEjecucion::Terminado;
+        e.fin = Some(Utc::now().to_rfc3339());
+        e.fase = None;
+        e.inicio_fase = None;
+        e.contenedores.clear();
+    });
+}
+
+async fn rampa(app: &AppState, fin: Instant, categoria: &str, desde: u32, hasta: u32, intensidad: u32) {
+    let inicio = Instant::now();
+    let pasos = desde.abs_diff(hasta);
+    // Con 1 → 4 en 120 s queda 1, 2, 3 y 4 contenedores cada 30 s
+    let intervalo = (fin - inicio) / (pasos + 1);
+
+    let mut vivos = Vec::new();
+    for _ in 0..desde {
+        vivos.extend(lanzar(app, categoria, intensidad, restante(fin)).await);
+    }
+    for paso in 1..=pasos {
+        sleep_until(inicio + intervalo * paso).await;
+        if hasta > desde {
+            vivos.extend(lanzar(app, categoria, intensidad, restante(fin)).await);
+        } else if let Some(id) = vivos.pop() {
+            quitar(app, &id).await;
+        }
+    }
+}
+
+async fn constante(app: &AppState, fin: Instant, categoria: &str, cantidad: u32, intensidad: u32) {
+    let mut vivos: Vec<String> = Vec::new();
+    loop {
+        // Se quitan los que ya no corren y se reponen
+        let mut siguen = Vec::new();
+        for id in vivos {
+            let corriendo = app.docker.inspect_container(&id, None).await.ok().and_then(|i| i.state).and_then(|s| s.running);
+            if corriendo == Some(true) {
+                siguen.push(id);
+            }
+        }
+        vivos = siguen;
+        while (vivos.len() as u32) < cantidad && restante(fin) > 1 {
+            match lanzar(app, categoria, intensidad, restante(fin)).await {
+                Some(id) => vivos.push(id),
+                None => break,
+            }
+        }
+
+        if Instant::now() + REVISION_CONSTANTE >= fin {
+            return;
+        }
+        sleep(REVISION_CONSTANTE).await;
+    }
+}
+
+fn restante(fin: Instant) -> u32 {
+    fin.saturating_duration_since(Instant::now()).as_secs().max(1) as u32
+}
+
+/// Lanza un contenedor de stress y lo deja protegido del reconciliador mientras dure el escenario
+async fn lanzar(app: &AppState, categoria: &str, intensidad: u32, segundos: u32) -> Option<String> {
+    let args = stress::argumentos_stress(categoria, intensidad, segundos)?;
+    let (id, name) = match stress::lanzar_contenedor(&app.docker, categoria, &args).await {
+        Ok(creado) => creado,
+        Err(e) => {
+            app.escenarios.registrar_error(format!("No se pudo lanzar un contenedor de {}: {}", categoria, e));
+            return None;
+        }
+    };
+
+    app.escenarios.actualizar(|e| {
+        e.contenedores.insert(id.clone());
+        e.contenedores_lanzados += 1;
+    });
+
+    let logs = vec![ContainerLog {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        category: categoria.to_string(),
+        name,
+        action: "creado".to_string(),
+    }];
+    app.monitor.registrar_eventos(&logs);
+    if let Ok(Err(e)) = web::block(move || enviar_container_logs(&logs)).await {
+        log::error!("No se pudo escribir el log del contenedor: {}", e);
+    }
+    Some(id)
+}
+
+async fn quitar(app: &AppState, id: &str) {
+    app.escenarios.actualizar(|e| {
+        e.contenedores.remove(id);
+    });
+    let opciones = RemoveContainerOptions { force: true, ..Default::default() };
+    if let Err(e) = app.docker.remove_container(id, Some(opciones)).await {
+        app.escenarios.registrar_error(format!("No se pudo quitar el contenedor {}: {}", id, e));
+    }
+}
+
+fn error_json(status: StatusCode, mensaje: String) -> HttpResponse {
+    HttpResponse::build(status).json(json!({ "error": mensaje }))
+}
+
+/// `GET /api/escenarios`, los escenarios del directorio y la última corrida
+pub async fn get_escenarios(data: web::Data<AppState>) -> impl Responder {
+    let escenarios = data.escenarios.clone();
+    let disponibles = web::block(move || escenarios.disponibles()).await.unwrap_or_default();
+    HttpResponse::Ok().json(json!({ "disponibles": disponibles, "ejecucion": data.escenarios.ejecucion() }))
+}
+
+/// `POST /api/escenarios/{nombre}/iniciar`
+pub async fn iniciar_escenario(data: web::Data<AppState>, nombre: web::Path<String>) -> impl Responder {
+    let nombre = nombre.into_inner();
+    let escenarios = data.escenarios.clone();
+    let nombre_bloque = nombre.clone();
+
+    let escenario = match web::block(move || escenarios.cargar(&nombre_bloque)).await {
+        Ok(Ok(escenario)) => escenario,
+        Ok(Err((status, mensaje))) => return error_json(status, mensaje),
+        Err(e) => return error_json(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+
+    match data.escenarios.iniciar(&data, &nombre, escenario) {
+        Ok(ejecucion) => {
+            log::info!("Escenario '{}' iniciado desde la API", nombre);
+            HttpResponse::Accepted().json(ejecucion)
+        }
+        Err((status, mensaje)) => error_json(status, mensaje),
+    }
+}
+
+/// `POST /api/escenarios/detener`, corta el escenario en curso y quita sus contenedores
+pub async fn detener_escenario(data: web::Data<AppState>) -> impl Responder {
+    let (ejecucion, contenedores) = match data.escenarios.detener() {
+        Ok(detenido) => detenido,
+        Err((status, mensaje)) => return error_json(status, mensaje),
+    };
+
+    log::info!("Escenario '{}' detenido desde la API", ejecucion.escenario);
+    for id in &contenedores {
+        let opciones = RemoveContainerOptions { force: true, ..Default::default() };
+        if let Err(e) = data.docker.remove_container(id, Some(opciones)).await {
+            log::error!("No se pudo quitar el contenedor {} del escenario: {}", id, e);
+        }
+    }
+    HttpResponse::Ok().json(ejecucion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escenario(yaml: &str) -> Escenario {
+        parsear(Path::new("prueba.yaml"), yaml).unwrap()
+    }
+
+    fn error(yaml: &str) -> String {
+        validar(&escenario(yaml)).unwrap_err()
+    }
+
+    #[test]
+    fn escenario_valido_en_yaml_y_toml() {
+        let yaml = escenario(
+            "fases:
+  - { nombre: base, duracion_segundos: 30, tipo: reposo }
+  - { nombre: subida, duracion_segundos: 60, tipo: rampa, categoria: cpu, desde: 0, hasta: 4 }
+  - { nombre: pico, duracion_segundos: 60, tipo: rafaga, categoria: ram, cantidad: 3, duracion_contenedor_segundos: 20 }
+  - { nombre: meseta, duracion_segundos: 60, tipo: constante, categoria: io, cantidad: 2, intensidad: 2 }
+",
+        );
+        assert!(validar(&yaml).is_ok());
+
+        let toml = parsear(
+            Path::new("prueba.toml"),
+            "[[fases]]\nnombre = \"base\"\nduracion_segundos = 10\ntipo = \"reposo\"\n",
+        )
+        .unwrap();
+        assert!(validar(&toml).is_ok());
+    }
+
+    #[test]
+    fn fases_vacias_sin_nombre_o_repetidas() {
+        assert!(error("fases: []").contains("no tiene fases"));
+        assert!(error("fases: [{ nombre: ' ', duracion_segundos: 5, tipo: reposo }]").contains("necesitan un nombre"));
+        let repetidas = "fases: [{ nombre: a, duracion_segundos: 5, tipo: reposo }, { nombre: a, duracion_segundos: 5, tipo: reposo }]";
+        assert!(error(repetidas).contains("dos fases con el nombre 'a'"));
+    }
+
+    #[test]
+    fn limites_de_cada_fase() {
+        assert!(error("fases: [{ nombre: a, duracion_segundos: 0, tipo: reposo }]").contains("duracion_segundos"));
+        let larga = format!("fases: [{{ nombre: a, duracion_segundos: {}, tipo: reposo }}]", stress::MAX_DURACION + 1);
+        assert!(error(&larga).contains("duracion_segundos"));
+
+        let casos = [
+            ("tipo: rampa, categoria: cpu, desde: 2, hasta: 2", "son iguales"),
+            ("tipo: rampa, categoria: gpu, desde: 0, hasta: 2", "Categoría 'gpu'"),
+            ("tipo: rafaga, categoria: cpu, cantidad: 0", "cantidad debe estar entre 1"),
+            ("tipo: rafaga, categoria: cpu, cantidad: 1, duracion_contenedor_segundos: 11", "duracion_contenedor_segundos"),
+            ("tipo: rafaga, categoria: cpu, cantidad: 1, duracion_contenedor_segundos: 0", "duracion_contenedor_segundos"),
+            ("tipo: constante, categoria: disk, cantidad: 1, intensidad: 0", "intensidad debe estar entre 1"),
+        ];
+        for (tipo, esperado) in casos {
+            let mensaje = error(&format!("fases: [{{ nombre: f, duracion_segundos: 10, {} }}]", tipo));
+            assert!(mensaje.starts_with("Fase 'f': ") && mensaje.contains(esperado), "{}", mensaje);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+use tokio::sync::mpsc::error::TrySendError;
+
+use crate::{AppState, RequestLog};
+
+// Si la cola se llena los logs nuevos se descartan en vez de frenar las peticiones
+const CAPACIDAD_COLA: usize = 1024;
+const LOTE_MAX: usize = 256;
+// Tiempo máximo que un log espera en memoria antes de escribirse
+const ESPERA_LOTE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Default)]
+struct Contadores {
+    encolados: AtomicU64,
+    escritos: AtomicU64,
+    descartados_cola_llena: AtomicU64,
+    // La tarea que escribe ya no existe, por el cierre o porque terminó con un panic
+    descartados_escritor_cerrado: AtomicU64,
+    descartados_error: AtomicU64,
+    lotes: AtomicU64,
+    errores_escritura: AtomicU64,
+    max_en_cola: AtomicUsize,
+    ultima_escritura: AtomicU64,
+}
+
+/// Estado del escritor, se sirve en `GET /logs/escritor`
+#[derive(Debug, Clone, Serialize)]
+pub struct EstadoEscritor {
+    pub archivo: String,
+    pub capacidad_cola: usize,
+    pub en_cola: usize,
+    pub max_en_cola: usize,
+    pub encolados: u64,
+    pub escritos: u64,
+    pub descartados_cola_llena: u64,
+    pub descartados_escritor_cerrado: u64,
+    pub descartados_error: u64,
+    /// `false` si la tarea que escribe terminó, los logs nuevos ya no llegan al archivo
+    pub escritor_activo: bool,
+    pub lotes: u64,
+    pub errores_escritura: u64,
+    pub ultima_escritura: Option<u64>,
+    pub ultimo_error: Option<String>,
+}
+
+/// Recibe los `RequestLog` del middleware sin bloquear y los escribe por lotes
+/// como NDJSON en segundo plano
+pub struct EscritorLogs {
+    archivo: PathBuf,
+    tx: mpsc::Sender<RequestLog>,
+    contadores: Arc<Contadores>,
+    ultimo_error: Arc<Mutex<Option<String>>>,
+    cierre: Arc<Notify>,
+    tarea: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl EscritorLogs {
+    /// Crea el escritor y arranca la tarea que escribe en `archivo`, necesita un runtime de tokio
+    pub fn iniciar(archivo: &str) -> Self {
+        let (tx, rx) = mpsc::channel(CAPACIDAD_COLA);
+        let escritor = EscritorLogs {
+            archivo: PathBuf::from(archivo),
+            tx,
+            contadores: Arc::new(Contadores::default()),
+            ultimo_error: Arc::new(Mutex::new(None)),
+            cierre: Arc::new(Notify::new()),
+            tarea: Mutex::new(None),
+        };
+
+        let tarea = tokio::spawn(escribir_lotes(
+            rx,
+            escritor.archivo.clone(),
+            escritor.contadores.clone(),
+            escritor.ultimo_error.clone(),
+            escritor.cierre.clone(),
+        ));
+        *escritor.tarea.lock().unwrap() = Some(tarea);
+        escritor
+    }
+
+    /// Deja de aceptar logs y espera a que se escriba lo que quedó en la cola
+    pub async fn cerrar(&self) -> Result<(), tokio::task::JoinError> {
+        self.cierre.notify_one();
+        let tarea = self.tarea.lock().unwrap().take();
+        match tarea {
+            Some(tarea) => tarea.await,
+            None => Ok(()),
+        }
+    }
+
+    /// Encola un log, nunca espera
+    pub fn registrar(&self, log: RequestLog) {
+        match self.tx.try_send(log) {
+            Ok(()) => {
+                self.contadores.encolados.fetch_add(1, Ordering::Relaxed);
+                let en_cola = CAPACIDAD_COLA - self.tx.capacity();
+                self.contadores.max_en_cola.fetch_max(en_cola, Ordering::Relaxed);
+            }
+            // Solo se avisa del primero y luego cada 1000 para no inundar la consola
+            Err(TrySendError::Full(_)) => {
+                let descartados = self.contadores.descartados_cola_llena.fetch_add(1, Ordering::Relaxed);
+                if descartados.is_multiple_of(1000) {
+                    log::warn!("Cola de logs llena, {} logs descartados", descartados + 1);
+                }
+            }
+            Err(TrySendError::Closed(_)) => {
+                let descartados = self.contadores.descartados_escritor_cerrado.fetch_add(1, Ordering::Relaxed);
+                if descartados.is_multiple_of(1000) {
+                    log::warn!("El escritor de logs no está corriendo, {} logs descartados", descartados + 1);
+                }
+            }
+        }
+    }
+
+    pub fn estado(&self) -> EstadoEscritor {
+        let c = &self.contadores;
+        let ultima_escritura = c.ultima_escritura.load(Ordering::Relaxed);
+        EstadoEscritor {
+            archivo: self.archivo.display().to_string(),
+            capacidad_cola: CAPACIDAD_COLA,
+            en_cola: CAPACIDAD_COLA - self.tx.capacity(),
+            max_en_cola: c.max_en_cola.load(Ordering::Relaxed),
+            encolados: c.encolados.load(Ordering::Relaxed),
+            escritos: c.escritos.load(Ordering::Relaxed),
+            descartados_cola_llena: c.descartados_cola_llena.load(Ordering::Relaxed),
+            descartados_escritor_cerrado: c.descartados_escritor_cerrado.load(Ordering::Relaxed),
+            descartados_error: c.descartados_error.load(Ordering::Relaxed),
+            escritor_activo: !self.tx.is_closed(),
+            lotes: c.lotes.load(Ordering::Relaxed),
+            errores_escritura: c.errores_escritura.load(Ordering::Relaxed),
+            ultima_escritura: (ultima_escritura > 0).then_some(ultima_escritura),
+            ultimo_error: self.ultimo_error.lock().unwrap().clone(),
+        }
+    }
+}
+
+async fn escribir_lotes(
+    mut rx: mpsc::Receiver<RequestLog>,
+    archivo: PathBuf,
+    contadores: Arc<Contadores>,
+    ultimo_error: Arc<Mutex<Option<String>>>,
+    cierre: Arc<Notify>,
+) {
+    // Termina cuando se cierran todos los Sender o al cerrar, después de escribir lo que quedó en la cola
+    loop {
+        let primero = tokio::select! {
+            log = rx.recv() => log,
+            _ = cierre.notified() => {
+                rx.close();
+                rx.recv().await
+            }
+        };
+        let primero = match primero {
+            Some(log) => log,
+            None => break,
+        };
+
+        let mut lote = vec![primero];
+        let limite = tokio::time::Instant::now() + ESPERA_LOTE;
+        while lote.len() < LOTE_MAX {
+            match tokio::time::timeout_at(limite, rx.recv()).await {
+                Ok(Some(log)) => lote.push(log),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let cantidad = lote.len() as u64;
+        let destino = archivo.clone();
+        let resultado = tokio::task::spawn_blocking(move || escribir_ndjson(&destino, &lote)).await;
+
+        match resultado {
+            Ok(Ok(())) => {
+                contadores.escritos.fetch_add(cantidad, Ordering::Relaxed);
+                contadores.lotes.fetch_add(1, Ordering::Relaxed);
+                let ahora = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+                contadores.ultima_escritura.store(ahora, Ordering::Relaxed);
+                *ultimo_error.lock().unwrap() = None;
+            }
+            Ok(Err(e)) => registrar_error(&contadores, &ultimo_error, cantidad, e.to_string()),
+            Err(e) => registrar_error(&contadores, &ultimo_error, cantidad, e.to_string()),
+        }
+    }
+}
+
+fn registrar_error(contadores: &Contadores, ultimo_error: &Mutex<Option<String>>, cantidad: u64, error: String) {
+    log::error!("No se pudieron escribir {} logs de peticiones: {}", cantidad, error);
+    contadores.errores_escritura.fetch_add(1, Ordering::Relaxed);
+    contadores.descartados_error.fetch_add(cantidad, Ordering::Relaxed);
+    *ultimo_error.lock().unwrap() = Some(error);
+}
+
+fn escribir_ndjson(archivo: &Path, lote: &[RequestLog]) -> std::io::Result<()> {
+    if let Some(parent) = archivo.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut lineas = String::new();
+    for log in lote {
+        lineas.push_str(&serde_json::to_string(log)?);
+        lineas.push('\n');
+    }
+
+    // Un solo write por lote para que /logs nunca vea una línea a medias de otro lote
+    let mut file = OpenOptions::new().create(true).append(true).open(archivo)?;
+    file.write_all(lineas.as_bytes())
+}
+
+/// `GET /logs/escritor`
+pub async fn get_estado_escritor(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(data.escritor_logs.estado())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log() -> RequestLog {
+        RequestLog { timestamp: 1, method: "GET".to_string(), path: "/".to_string(), status: 200, ip: "127.0.0.1".to_string() }
+    }
+
+    #[tokio::test]
+    async fn despues_de_cerrar_se_cuenta_como_escritor_cerrado() {
+        let dir = tempfile::tempdir().unwrap();
+        let archivo = dir.path().join("requests.log");
+        let escritor = EscritorLogs::iniciar(archivo.to_str().unwrap());
+        escritor.registrar(log());
+        escritor.cerrar().await.unwrap();
+        escritor.registrar(log());
+
+        let estado = escritor.estado();
+        assert_eq!(estado.escritos, 1);
+        assert_eq!(estado.descartados_escritor_cerrado, 1);
+        assert_eq!(estado.descartados_cola_llena, 0);
+        assert!(!estado.escritor_activo);
+        assert_eq!(fs::read_to_string(archivo).unwrap().lines().count(), 1);
+    }
+}
@@ -0,0 +1,310 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use actix_ws::Message;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::alertas::NotificacionAlerta;
+use crate::dashboard::ContenedorVivo;
+use crate::escenarios::FaseActiva;
+use crate::{AppState, SystemStats};
+
+/// Todo lo que publica el monitor en cada ciclo
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "tipo", rename_all = "snake_case")]
+pub enum EventoMonitor {
+    /// Lo leído del módulo de kernel
+    Muestra {
+        timestamp: u64,
+        system: SystemStats,
+        containers: Vec<ContenedorVivo>,
+        /// Fase del escenario de carga que estaba corriendo
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fase: Option<FaseActiva>,
+    },
+    /// Lo que decidió el reconciliador con cada contenedor de Docker
+    Decision {
+        timestamp: u64,
+        categoria: String,
+        id: String,
+        nombre: String,
+        accion: String,
+        error: Option<String>,
+    },
+    /// Cambio de estado de una regla de alerta
+    Alerta(NotificacionAlerta),
+}
+
+impl EventoMonitor {
+    fn tipo(&self) -> &'static str {
+        match self {
+            EventoMonitor::Muestra { .. } => "muestra",
+            EventoMonitor::Decision { .. } => "decision",
+            EventoMonitor::Alerta(_) => "alerta",
+        }
+    }
+}
+
+pub struct BusEventos {
+    canal: broadcast::Sender<EventoMonitor>,
+}
+
+impl BusEventos {
+    pub fn new() -> Self {
+        let (canal, _) = broadcast::channel(256);
+        BusEventos { canal }
+    }
+
+    pub fn publicar(&self, evento: EventoMonitor) {
+        // Si no hay suscriptores send falla, no es un error
+        let _ = self.canal.send(evento);
+    }
+
+    pub fn suscribir(&self) -> broadcast::Receiver<EventoMonitor> {
+        self.canal.subscribe()
+    }
+}
+
+/// Filtro de `/stream`, ambos campos son listas separadas por coma.
+/// Ej: `/stream?tipo=decision&categoria=ram,cpu`
+#[derive(Debug, Default, Deserialize)]
+pub struct FiltroEventos {
+    tipo: Option<String>,
+    categoria: Option<String>,
+}
+
+impl FiltroEventos {
+    fn lista(valor: &Option<String>) -> Option<Vec<String>> {
+        valor.as_ref().map(|v| {
+            v.split(',')
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+    }
+
+    /// Devuelve el evento que le toca al suscriptor o None si no le interesa.
+    /// En las muestras solo se dejan los contenedores de las categorías pedidas.
+    fn aplicar(&self, evento: &EventoMonitor) -> Option<EventoMonitor> {
+        if let Some(tipos) = Self::lista(&self.tipo)
+            && !tipos.iter().any(|t| t == evento.tipo())
+        {
+            return None;
+        }
+
+        let categorias = match Self::lista(&self.categoria) {
+            Some(categorias) => categorias,
+            None => return Some(evento.clone()),
+        };
+
+        match evento {
+            EventoMonitor::Muestra { timestamp, system, containers, fase } => Some(EventoMonitor::Muestra {
+                timestamp: *timestamp,
+                system: system.clone(),
+                fase: fase.clone(),
+                containers: containers
+                    .iter()
+                    .filter(|c| c.categoria.as_ref().is_some_and(|cat| categorias.contains(cat)))
+                    .cloned()
+                    .collect(),
+            }),
+            EventoMonitor::Decision { categoria, .. } => {
+                if categorias.contains(categoria) {
+                    Some(evento.clone())
+                } else {
+                    None
+                }
+            }
+            EventoMonitor::Alerta(alerta) => {
+                if alerta.categoria().is_some_and(|c| categorias.iter().any(|cat| cat == c)) {
+                    Some(evento.clone())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Espera el siguiente evento que pase el filtro
+async fn siguiente(rx: &mut broadcast::Receiver<EventoMonitor>, filtro: &FiltroEventos) -> Option<EventoMonitor> {
+    loop {
+        match rx.recv().await {
+            Ok(evento) => {
+                if let Some(evento) = filtro.aplicar(&evento) {
+                    return Some(evento);
+                }
+            }
+            Err(RecvError::Lagged(perdidos)) => {
+                log::warn!("Suscriptor atrasado, se perdieron {} eventos", perdidos);
+            }
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Eventos del monitor por Server-Sent Events
+pub async fn stream_sse(data: web::Data<AppState>, filtro: web::Query<FiltroEventos>) -> impl Responder {
+    let rx = data.eventos.suscribir();
+    let inicio = futures::stream::once(async { Ok::<_, actix_web::Error>(web::Bytes::from_static(b": conectado\n\n")) });
+
+    let stream = futures::stream::unfold((rx, filtro.into_inner()), |(mut rx, filtro)| async move {
+        let evento = siguiente(&mut rx, &filtro).await?;
+        let json = serde_json::to_string(&evento).unwrap_or_default();
+        let mensaje = web::Bytes::from(format!("event: {}\ndata: {}\n\n", evento.tipo(), json));
+        Some((Ok::<_, actix_web::Error>(mensaje), (rx, filtro)))
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(inicio.chain(stream))
+}
+
+/// Eventos del monitor por WebSocket, un mensaje de texto JSON por evento
+pub async fn stream_ws(
+    req: HttpRequest,
+    body: web::Payload,
+    data: web::Data<AppState>,
+    filtro: web::Query<FiltroEventos>,
+) -> actix_web::Result<HttpResponse> {
+    let (response, mut session, mut mensajes) = actix_ws::handle(&req, body)?;
+    let mut rx = data.eventos.suscribir();
+    let filtro = filtro.into_inner();
+
+    actix_web::rt::spawn(async move {
+        loop {
+            tokio::select! {
+                evento = siguiente(&mut rx, &filtro) => {
+                    let evento = match evento {
+                        Some(evento) => evento,
+                        None => break,
+                    };
+                    let json = serde_json::to_string(&evento).unwrap_or_default();
+                    if session.text(json).await.is_err() {
+                        return;
+                    }
+                }
+                mensaje = mensajes.recv() => {
+                    match mensaje {
+                        Some(Ok(Message::Ping(bytes))) => {
+                            if session.pong(&bytes).await.is_err() {
+                                return;
+                            }
+                        }
+                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                        Some(Ok(_)) => {}
+                    }
+                }
+            }
+        }
+
+        let _ = session.close(None).await;
+    });
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alertas::{EstadoAlerta, Operador};
+    use crate::ContainerInfo;
+
+    fn filtro(tipo: Option<&str>, categoria: Option<&str>) -> FiltroEventos {
+        FiltroEventos { tipo: tipo.map(str::to_string), categoria: categoria.map(str::to_string) }
+    }
+
+    fn contenedor(pid: u32, categoria: Option<&str>) -> ContenedorVivo {
+        ContenedorVivo {
+            info: ContainerInfo {
+                pid,
+                name: "stress".to_string(),
+                cmdline: String::new(),
+                memory_rss: 0,
+                memory_percent: 0,
+                virtual_memory: 0,
+                cpu_percent: 0,
+                disk_usage: 0,
+                io_read_bytes: 0,
+                io_write_bytes: 0,
+            },
+            categoria: categoria.map(str::to_string),
+        }
+    }
+
+    fn muestra() -> EventoMonitor {
+        EventoMonitor::Muestra {
+            timestamp: 1,
+            system: SystemStats { ram_total: 100, ram_libre: 50, ram_ocupada: 50, cpu_usada: 10 },
+            containers: vec![contenedor(1, Some("cpu")), contenedor(2, Some("ram")), contenedor(3, None)],
+            fase: None,
+        }
+    }
+
+    fn decision(categoria: &str) -> EventoMonitor {
+        EventoMonitor::Decision {
+            timestamp: 1,
+            categoria: categoria.to_string(),
+            id: "abc".to_string(),
+            nombre: format!("stress_{}_1", categoria),
+            accion: "eliminado".to_string(),
+            error: None,
+        }
+    }
+
+    fn alerta(metrica: &str) -> EventoMonitor {
+        EventoMonitor::Alerta(NotificacionAlerta {
+            regla: "regla".to_string(),
+            metrica: metrica.to_string(),
+            operador: Operador::Mayor,
+            umbral: 1.0,
+            valor: 2.0,
+            estado: EstadoAlerta::Disparada,
+            timestamp: 1,
+        })
+    }
+
+    fn pids(evento: Option<EventoMonitor>) -> Vec<u32> {
+        match evento {
+            Some(EventoMonitor::Muestra { containers, .. }) => containers.iter().map(|c| c.info.pid).collect(),
+            otro => panic!("se esperaba una muestra: {:?}", otro),
+        }
+    }
+
+    #[test]
+    fn sin_filtro_pasa_todo() {
+        let filtro = FiltroEventos::default();
+        assert_eq!(pids(filtro.aplicar(&muestra())), vec![1, 2, 3]);
+        assert!(filtro.aplicar(&decision("io")).is_some());
+        assert!(filtro.aplicar(&alerta("cpu")).is_some());
+    }
+
+    #[test]
+    fn filtra_por_tipo() {
+        let filtro = filtro(Some(" Decision , alerta"), None);
+        assert!(filtro.aplicar(&muestra()).is_none());
+        assert!(filtro.aplicar(&decision("cpu")).is_some());
+        assert!(filtro.aplicar(&alerta("cpu")).is_some());
+    }
+
+    #[test]
+    fn filtra_por_categoria() {
+        let filtro = filtro(None, Some("ram,io"));
+        assert!(filtro.aplicar(&decision("io")).is_some());
+        assert!(filtro.aplicar(&decision("cpu")).is_none());
+        assert!(filtro.aplicar(&alerta("contenedores:ram")).is_some());
+        assert!(filtro.aplicar(&alerta("contenedores:cpu")).is_none());
+        // Las alertas del sistema no tienen categoría
+        assert!(filtro.aplicar(&alerta("cpu")).is_none());
+    }
+
+    #[test]
+    fn en_las_muestras_solo_quedan_los_contenedores_pedidos() {
+        assert_eq!(pids(filtro(None, Some("ram")).aplicar(&muestra())), vec![2]);
+        assert_eq!(pids(filtro(Some("muestra"), Some("CPU,ram")).aplicar(&muestra())), vec![1, 2]);
+        // Una lista vacía no deja ningún contenedor, pero la muestra sigue llegando
+        assert!(pids(filtro(None, Some(",")).aplicar(&muestra())).is_empty());
+    }
+}
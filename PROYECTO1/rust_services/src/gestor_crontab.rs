@@ -0,0 +1,352 @@
+use std::error::Error;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+type Resultado<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+// Solo se toca lo que está entre estas dos líneas, el resto del crontab es del usuario
+pub const MARCA_INICIO: &str = "# >>> rust_services (no editar este bloque) >>>";
+pub const MARCA_FIN: &str = "# <<< rust_services <<<";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EstadoBloque {
+    Ausente,
+    AlDia,
+    /// El bloque existe pero no coincide: lo editaron, está duplicado, le falta una
+    /// marca o quedaron líneas nuestras fuera de él (versiones viejas hacían append)
+    Desviado,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cambio {
+    SinCambios,
+    Instalado,
+    Reparado,
+    Eliminado,
+}
+
+/// Administra el bloque de este servicio dentro del crontab del usuario
+pub struct GestorCrontab {
+    // Binario de crontab, se puede cambiar para probar con uno falso
+    comando: String,
+    entradas: Vec<String>,
+}
+
+/// Las líneas del crontab separadas en las del usuario y las de nuestro bloque
+struct TablaSeparada {
+    ajenas: Vec<String>,
+    bloque: Vec<String>,
+    desviado: bool,
+}
+
+impl GestorCrontab {
+    pub fn new(comando: &str, entradas: Vec<String>) -> Self {
+        GestorCrontab { comando: comando.to_string(), entradas }
+    }
+
+    /// Las dos entradas de `script.sh`, una al inicio de cada minuto y otra a los 30 s
+    pub fn para_script(script_path: &str) -> Self {
+        GestorCrontab::new(
+            "crontab",
+            vec![
+                format!("* * * * * /bin/bash {}", script_path),
+                format!("* * * * * sleep 30 && /bin/bash {}", script_path),
+            ],
+        )
+    }
+
+    pub fn revisar(&self) -> Resultado<EstadoBloque> {
+        let tabla = self.separar(&self.leer()?);
+        Ok(if tabla.desviado {
+            EstadoBloque::Desviado
+        } else if tabla.bloque.is_empty() {
+            EstadoBloque::Ausente
+        } else {
+            EstadoBloque::AlDia
+        })
+    }
+
+    /// Deja el bloque igual a las entradas. Si ya está al día no escribe nada.
+    pub fn instalar(&self) -> Resultado<Cambio> {
+        let actual = self.leer()?;
+        let tabla = self.separar(&actual);
+        if !tabla.desviado && !tabla.bloque.is_empty() {
+            return Ok(Cambio::SinCambios);
+        }
+
+        let mut lineas = tabla.ajenas;
+        lineas.push(MARCA_INICIO.to_string());
+        lineas.extend(self.entradas.iter().cloned());
+        lineas.push(MARCA_FIN.to_string());
+        self.escribir(&unir(&lineas))?;
+        Ok(if tabla.desviado { Cambio::Reparado } else { Cambio::Instalado })
+    }
+
+    /// Quita solo nuestro bloque (y líneas nuestras sueltas), lo demás queda como estaba
+    pub fn desinstalar(&self) -> Resultado<Cambio> {
+        let actual = self.leer()?;
+        let tabla = self.separar(&actual);
+        if tabla.bloque.is_empty() && !tabla.desviado {
+            return Ok(Cambio::SinCambios);
+        }
+
+        self.escribir(&unir(&tabla.ajenas))?;
+        Ok(Cambio::Eliminado)
+    }
+
+    fn separar(&self, contenido: &str) -> TablaSeparada {
+        let mut tabla = TablaSeparada { ajenas: Vec::new(), bloque: Vec::new(), desviado: false };
+        let mut dentro = false;
+        let mut bloques = 0;
+        // Dónde empieza en `bloque` el último bloque abierto
+        let mut inicio_abierto = 0;
+
+        for linea in contenido.lines() {
+            let recortada = linea.trim();
+            if recortada == MARCA_INICIO {
+                // Un inicio sin cerrar el anterior también es desvío
+                tabla.desviado |= dentro;
+                dentro = true;
+                bloques += 1;
+                inicio_abierto = tabla.bloque.len();
+            } else if recortada == MARCA_FIN {
+                tabla.desviado |= !dentro;
+                dentro = false;
+            } else if dentro {
+                tabla.bloque.push(linea.to_string());
+            } else if self.entradas.iter().any(|e| e == recortada) {
+                // Restos de cuando las entradas se agregaban sin marcas
+                tabla.desviado = true;
+            } else {
+                tabla.ajenas.push(linea.to_string());
+            }
+        }
+
+        // Sin marca de fin no se sabe dónde termina el bloque, de lo que quedó después
+        // del inicio solo son nuestras las entradas, lo demás vuelve a ser del usuario
+        if dentro {
+            let (nuestras, ajenas): (Vec<String>, Vec<String>) = tabla
+                .bloque
+                .split_off(inicio_abierto)
+                .into_iter()
+                .partition(|linea| self.entradas.iter().any(|e| e == linea.trim()));
+            tabla.bloque.extend(nuestras);
+            tabla.ajenas.extend(ajenas);
+        }
+
+        tabla.desviado |= dentro || bloques > 1 || (bloques == 1 && tabla.bloque != self.entradas);
+        tabla
+    }
+
+    fn leer(&self) -> Resultado<String> {
+        let output = Command::new(&self.comando)
+            .arg("-l")
+            .output()
+            .map_err(|e| format!("No se pudo ejecutar {}: {}", self.comando, e))?;
+
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+        }
+
+        // Un usuario sin crontab no es un error, es una tabla vacía
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("no crontab") {
+            Ok(String::new())
+        } else {
+            Err(format!("{} -l falló: {}", self.comando, stderr.trim()).into())
+        }
+    }
+
+    fn escribir(&self, contenido: &str) -> Resultado<()> {
+        let mut child = Command::new(&self.comando)
+            .arg("-")
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("No se pudo ejecutar {}: {}", self.comando, e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(contenido.as_bytes())?;
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(format!("{} - falló: {}", self.comando, String::from_utf8_lossy(&output.stderr).trim()).into());
+        }
+        Ok(())
+    }
+}
+
+fn unir(lineas: &[String]) -> String {
+    if lineas.is_empty() {
+        return String::new();
+    }
+    let mut contenido = lineas.join("\n");
+    contenido.push('\n');
+    contenido
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+
+    /// Un `crontab` falso que guarda la tabla en `tabla` junto a él y cuenta las escrituras
+    fn crontab_falso(dir: &Path) -> PathBuf {
+        let script = dir.join("crontab");
+        fs::write(
+            &script,
+            r#"#!/bin/sh
+dir=$(dirname "$0")
+case "$1" in
+    -l)
+        if [ ! -f "$dir/tabla" ]; then
+            echo "no crontab for prueba" >&2
+            exit 1
+        fi
+        cat "$dir/tabla"
+        ;;
+    -)
+        cat > "$dir/tabla"
+        echo x >> "$dir/escrituras"
+        ;;
+    *)
+        echo "uso: crontab -l | -" >&2
+        exit 2
+        ;;
+esac
+"#,
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        script
+    }
+
+    fn gestor(script: &Path) -> GestorCrontab {
+        GestorCrontab::new(script.to_str().unwrap(), vec!["* * * * * /bin/bash /opt/script.sh".to_string()])
+    }
+
+    fn tabla(dir: &Path) -> String {
+        fs::read_to_string(dir.join("tabla")).unwrap()
+    }
+
+    fn escrituras(dir: &Path) -> usize {
+        fs::read_to_string(dir.join("escrituras")).map(|s| s.lines().count()).unwrap_or(0)
+    }
+
+    #[test]
+    fn sin_crontab_es_una_tabla_vacia() {
+        let dir = tempfile::tempdir().unwrap();
+        let gestor = gestor(&crontab_falso(dir.path()));
+        assert_eq!(gestor.revisar().unwrap(), EstadoBloque::Ausente);
+        assert_eq!(gestor.desinstalar().unwrap(), Cambio::SinCambios);
+        assert_eq!(escrituras(dir.path()), 0);
+    }
+
+    #[test]
+    fn instalar_en_tabla_vacia_y_reinstalar_sin_duplicar() {
+        let dir = tempfile::tempdir().unwrap();
+        let gestor = gestor(&crontab_falso(dir.path()));
+
+        assert_eq!(gestor.instalar().unwrap(), Cambio::Instalado);
+        let esperado = format!("{}\n* * * * * /bin/bash /opt/script.sh\n{}\n", MARCA_INICIO, MARCA_FIN);
+        assert_eq!(tabla(dir.path()), esperado);
+        assert_eq!(gestor.revisar().unwrap(), EstadoBloque::AlDia);
+
+        assert_eq!(gestor.instalar().unwrap(), Cambio::SinCambios);
+        assert_eq!(tabla(dir.path()), esperado);
+        assert_eq!(escrituras(dir.path()), 1);
+    }
+
+    #[test]
+    fn desinstalar_deja_las_lineas_del_usuario() {
+        let dir = tempfile::tempdir().unwrap();
+        let gestor = gestor(&crontab_falso(dir.path()));
+        fs::write(dir.path().join("tabla"), "MAILTO=yo@ejemplo.com\n0 3 * * * /usr/bin/backup\n").unwrap();
+
+        assert_eq!(gestor.instalar().unwrap(), Cambio::Instalado);
+        assert!(tabla(dir.path()).starts_with("MAILTO=yo@ejemplo.com\n0 3 * * * /usr/bin/backup\n"));
+
+        assert_eq!(gestor.desinstalar().unwrap(), Cambio::Eliminado);
+        assert_eq!(tabla(dir.path()), "MAILTO=yo@ejemplo.com\n0 3 * * * /usr/bin/backup\n");
+        assert_eq!(gestor.desinstalar().unwrap(), Cambio::SinCambios);
+    }
+
+    #[test]
+    fn repara_bloques_desviados() {
+        let dir = tempfile::tempdir().unwrap();
+        let gestor = gestor(&crontab_falso(dir.path()));
+        let esperado = format!("0 3 * * * /usr/bin/backup\n{}\n* * * * * /bin/bash /opt/script.sh\n{}\n", MARCA_INICIO, MARCA_FIN);
+
+        let desviadas = [
+            // Editado a mano
+            format!("0 3 * * * /usr/bin/backup\n{}\n*/5 * * * * /bin/bash /opt/script.sh\n{}\n", MARCA_INICIO, MARCA_FIN),
+            // Duplicado
+            format!("0 3 * * * /usr/bin/backup\n{i}\n* * * * * /bin/bash /opt/script.sh\n{f}\n{i}\n* * * * * /bin/bash /opt/script.sh\n{f}\n", i = MARCA_INICIO, f = MARCA_FIN),
+            // Sin marca de fin
+            format!("0 3 * * * /usr/bin/backup\n{}\n* * * * * /bin/bash /opt/script.sh\n", MARCA_INICIO),
+            // Entrada suelta de cuando se hacía append
+            "0 3 * * * /usr/bin/backup\n* * * * * /bin/bash /opt/script.sh\n".to_string(),
+        ];
+        for desviada in desviadas {
+            fs::write(dir.path().join("tabla"), &desviada).unwrap();
+            assert_eq!(gestor.revisar().unwrap(), EstadoBloque::Desviado, "{}", desviada);
+            assert_eq!(gestor.instalar().unwrap(), Cambio::Reparado, "{}", desviada);
+            assert_eq!(tabla(dir.path()), esperado);
+        }
+    }
+
+    #[test]
+    fn sin_marca_de_fin_no_se_pierden_las_lineas_del_usuario() {
+        let dir = tempfile::tempdir().unwrap();
+        let gestor = gestor(&crontab_falso(dir.path()));
+        let sin_cerrar = format!("0 3 * * * /usr/bin/backup\n{}\n* * * * * /bin/bash /opt/script.sh\n0 4 * * * /usr/bin/limpiar\n", MARCA_INICIO);
+
+        fs::write(dir.path().join("tabla"), &sin_cerrar).unwrap();
+        assert_eq!(gestor.instalar().unwrap(), Cambio::Reparado);
+        assert_eq!(
+            tabla(dir.path()),
+            format!("0 3 * * * /usr/bin/backup\n0 4 * * * /usr/bin/limpiar\n{}\n* * * * * /bin/bash /opt/script.sh\n{}\n", MARCA_INICIO, MARCA_FIN)
+        );
+
+        fs::write(dir.path().join("tabla"), &sin_cerrar).unwrap();
+        assert_eq!(gestor.desinstalar().unwrap(), Cambio::Eliminado);
+        assert_eq!(tabla(dir.path()), "0 3 * * * /usr/bin/backup\n0 4 * * * /usr/bin/limpiar\n");
+    }
+
+    #[test]
+    fn para_script_busca_crontab_en_el_path() {
+        let dir = tempfile::tempdir().unwrap();
+        crontab_falso(dir.path());
+        let path = std::env::var("PATH").unwrap_or_default();
+        // Ningún otro test busca `crontab` por nombre, anteponer el directorio no los afecta
+        unsafe { std::env::set_var("PATH", format!("{}:{}", dir.path().display(), path)) };
+
+        let gestor = GestorCrontab::para_script("/opt/script.sh");
+        let instalado = gestor.instalar();
+        let estado = gestor.revisar();
+        unsafe { std::env::set_var("PATH", path) };
+
+        assert_eq!(instalado.unwrap(), Cambio::Instalado);
+        assert_eq!(estado.unwrap(), EstadoBloque::AlDia);
+        assert_eq!(
+            tabla(dir.path()),
+            format!("{}\n* * * * * /bin/bash /opt/script.sh\n* * * * * sleep 30 && /bin/bash /opt/script.sh\n{}\n", MARCA_INICIO, MARCA_FIN)
+        );
+    }
+
+    #[test]
+    fn otros_errores_de_crontab_se_reportan() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("crontab");
+        fs::write(&script, "#!/bin/sh\necho 'permiso denegado' >&2\nexit 1\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let error = gestor(&script).revisar().unwrap_err();
+        assert!(error.to_string().contains("permiso denegado"), "{}", error);
+        let error = gestor(&dir.path().join("no-existe")).revisar().unwrap_err();
+        assert!(error.to_string().contains("No se pudo ejecutar"), "{}", error);
+    }
+}
@@ -0,0 +1,465 @@
+//! Grabación y reproducción de lo que el servicio lee de afuera: cada lectura de `/proc/sysinfo_*`,
+//! la lista de contenedores de Docker y lo que respondió Docker al eliminar. Con una grabación
+//! el monitor toma las mismas decisiones y arma el mismo reporte sin el módulo de kernel ni Docker.
+//!
+//! El archivo es NDJSON: la primera línea es un `Encabezado` y cada una de las demás una `Entrada`
+//! con los milisegundos desde que empezó la grabación.
+
+use bollard::container::{ListContainersOptions, RemoveContainerOptions};
+use bollard::models::ContainerSummary;
+use bollard::Docker;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const VERSION: u32 = 1;
+// Más lento que esto las esperas entre ciclos dejan de tener sentido
+const VELOCIDAD_MINIMA: f64 = 0.01;
+
+/// De dónde salen las lecturas, se elige una vez al arrancar
+#[derive(Debug, Clone)]
+pub enum Modo {
+    Directo,
+    /// Lee como siempre y guarda todo en un archivo nuevo dentro de `dir`
+    Grabar { dir: PathBuf },
+    /// Lee de una grabación. `velocidad` 1 es tiempo real, 10 diez veces más rápido y 0 sin esperas
+    Reproducir { archivo: PathBuf, velocidad: f64 },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Encabezado {
+    version: u32,
+    inicio: String,
+    sysinfo: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entrada {
+    ms: u64,
+    /// Lecturas del ciclo del monitor, son las que avanzan la reproducción
+    #[serde(default)]
+    monitor: bool,
+    #[serde(flatten)]
+    lectura: Lectura,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "tipo", rename_all = "snake_case")]
+enum Lectura {
+    /// El archivo de `/proc` tal cual, así también se reproducen los JSON que no se pudieron leer
+    Sysinfo { resultado: Resultado<String> },
+    Contenedores { resultado: Resultado<Vec<ContainerSummary>> },
+    Eliminar { id: String, resultado: Resultado<()> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Resultado<T> {
+    Ok(T),
+    Error(String),
+}
+
+impl<T: Clone, E: ToString> From<&Result<T, E>> for Resultado<T> {
+    fn from(resultado: &Result<T, E>) -> Self {
+        match resultado {
+            Ok(valor) => Resultado::Ok(valor.clone()),
+            Err(e) => Resultado::Error(e.to_string()),
+        }
+    }
+}
+
+enum Fuente {
+    Grabadora(Grabadora),
+    Reproduccion(Reproduccion),
+}
+
+static FUENTE: OnceLock<Fuente> = OnceLock::new();
+
+/// Elige de dónde leer para el resto del proceso, se llama antes de arrancar el monitor.
+/// Al grabar devuelve la ruta del archivo nuevo.
+pub fn instalar(modo: Modo, ruta_sysinfo: &str) -> Result<Option<PathBuf>, Box<dyn Error>> {
+    let (fuente, archivo) = match modo {
+        Modo::Directo => return Ok(None),
+        Modo::Grabar { dir } => {
+            let grabadora = Grabadora::crear(&dir, ruta_sysinfo)?;
+            let archivo = grabadora.archivo.clone();
+            (Fuente::Grabadora(grabadora), Some(archivo))
+        }
+        Modo::Reproducir { archivo, velocidad } => {
+            if !velocidad.is_finite() || (velocidad != 0.0 && velocidad < VELOCIDAD_MINIMA) {
+                return Err(format!("--velocidad debe ser 0 o al menos {}, no {}", VELOCIDAD_MINIMA, velocidad).into());
+            }
+            (Fuente::Reproduccion(Reproduccion::cargar(&archivo, velocidad)?), None)
+        }
+    };
+    FUENTE.set(fuente).map_err(|_| "La fuente de lecturas ya estaba instalada")?;
+    Ok(archivo)
+}
+
+pub fn reproduciendo() -> bool {
+    matches!(FUENTE.get(), Some(Fuente::Reproduccion(_)))
+}
+
+/// Hora para el reporte de la sesión. Al reproducir es la de la grabación en la muestra actual,
+/// así inicio, fases y duración no dependen de cuándo ni a qué velocidad se reproduce.
+pub(crate) fn ahora() -> DateTime<Utc> {
+    match FUENTE.get() {
+        Some(Fuente::Reproduccion(reproduccion)) => reproduccion.ahora(),
+        _ => Utc::now(),
+    }
+}
+
+/// Contenido del archivo de sysinfo. `monitor` dice si es la muestra del ciclo o una consulta suelta.
+pub(crate) fn sysinfo(ruta: &str, monitor: bool) -> Result<String, Box<dyn Error>> {
+    match FUENTE.get() {
+        Some(Fuente::Reproduccion(reproduccion)) => reproduccion.sysinfo(monitor).map_err(Into::into),
+        fuente => {
+            let resultado = fs::read_to_string(ruta);
+            if let Some(Fuente::Grabadora(grabadora)) = fuente {
+                grabadora.registrar(monitor, Lectura::Sysinfo { resultado: (&resultado).into() });
+            }
+            Ok(resultado?)
+        }
+    }
+}
+
+/// Todos los contenedores, también los detenidos
+pub(crate) async fn contenedores(docker: &Docker, monitor: bool) -> Result<Vec<ContainerSummary>, bollard::errors::Error> {
+    match FUENTE.get() {
+        Some(Fuente::Reproduccion(reproduccion)) => reproduccion.contenedores(monitor).map_err(error_docker),
+        fuente => {
+            let opciones = ListContainersOptions::<String> { all: true, ..Default::default() };
+            let resultado = docker.list_containers(Some(opciones)).await;
+            if let Some(Fuente::Grabadora(grabadora)) = fuente {
+                grabadora.registrar(monitor, Lectura::Contenedores { resultado: (&resultado).into() });
+            }
+            resultado
+        }
+    }
+}
+
+/// Elimina a la fuerza. Al reproducir no se toca Docker, se devuelve lo que respondió en la grabación.
+pub(crate) async fn eliminar(docker: &Docker, id: &str, monitor: bool) -> Result<(), bollard::errors::Error> {
+    match FUENTE.get() {
+        Some(Fuente::Reproduccion(reproduccion)) => reproduccion.eliminar(id, monitor).map_err(error_docker),
+        fuente => {
+            let opciones = RemoveContainerOptions { force: true, ..Default::default() };
+            let resultado = docker.remove_container(id, Some(opciones)).await;
+            if let Some(Fuente::Grabadora(grabadora)) = fuente {
+                grabadora.registrar(monitor, Lectura::Eliminar { id: id.to_string(), resultado: (&resultado).into() });
+            }
+            resultado
+        }
+    }
+}
+
+/// Espera hasta el siguiente ciclo del monitor. Al reproducir espera lo mismo que pasó en la grabación
+/// dividido entre la velocidad, y devuelve `false` cuando ya no quedan ciclos.
+pub(crate) async fn esperar_ciclo(intervalo: Duration) -> bool {
+    match FUENTE.get() {
+        Some(Fuente::Reproduccion(reproduccion)) => match reproduccion.espera_siguiente() {
+            Some(espera) => {
+                tokio::time::sleep(espera).await;
+                true
+            }
+            None => false,
+        },
+        _ => {
+            tokio::time::sleep(intervalo).await;
+            true
+        }
+    }
+}
+
+/// Resumen de la reproducción para cuando termina, `None` si no se está reproduciendo
+pub(crate) fn resumen() -> Option<String> {
+    match FUENTE.get() {
+        Some(Fuente::Reproduccion(reproduccion)) => Some(reproduccion.resumen()),
+        _ => None,
+    }
+}
+
+/// Los errores grabados vuelven como si Docker los hubiera respondido
+fn error_docker(message: String) -> bollard::errors::Error {
+    bollard::errors::Error::DockerResponseServerError { status_code: 500, message }
+}
+
+struct Grabadora {
+    archivo: PathBuf,
+    inicio: Instant,
+    salida: Mutex<BufWriter<File>>,
+}
+
+impl Grabadora {
+    fn crear(dir: &Path, ruta_sysinfo: &str) -> Result<Self, Box<dyn Error>> {
+        fs::create_dir_all(dir)?;
+        let ahora = chrono::Local::now();
+        let archivo = dir.join(format!("sesion_{}.ndjson", ahora.format("%Y%m%d_%H%M%S")));
+        let mut salida = BufWriter::new(File::create(&archivo)?);
+
+        let encabezado = Encabezado { version: VERSION, inicio: ahora.to_rfc3339(), sysinfo: ruta_sysinfo.to_string() };
+        writeln!(salida, "{}", serde_json::to_string(&encabezado)?)?;
+        salida.flush()?;
+
+        log::info!("Grabando las lecturas en {}", archivo.display());
+        Ok(Grabadora { archivo, inicio: Instant::now(), salida: Mutex::new(salida) })
+    }
+
+    fn registrar(&self, monitor: bool, lectura: Lectura) {
+        let entrada = Entrada { ms: self.inicio.elapsed().as_millis() as u64, monitor, lectura };
+        let linea = match serde_json::to_string(&entrada) {
+            Ok(linea) => linea,
+            Err(e) => {
+                log::error!("No se pudo serializar la lectura para la grabación: {}", e);
+                return;
+            }
+        };
+
+        // Se vacía en cada línea para que un cierre brusco no deje la grabación a medias
+        let mut salida = self.salida.lock().unwrap();
+        if let Err(e) = writeln!(salida, "{}", linea).and_then(|_| salida.flush()) {
+            log::error!("No se pudo escribir en {}: {}", self.archivo.display(), e);
+        }
+    }
+}
+
+struct Reproduccion {
+    velocidad: f64,
+    /// Hora en que empezó la grabación, los `ms` de cada entrada cuentan desde aquí
+    inicio: DateTime<Utc>,
+    /// Todas las lecturas en orden, para las consultas sueltas
+    entradas: Vec<Entrada>,
+    estado: Mutex<Cursor>,
+}
+
+#[derive(Default)]
+struct Cursor {
+    /// Posición de la reproducción, los ms de la última muestra del monitor
+    ms: u64,
+    ciclos: usize,
+    sysinfo: VecDeque<(u64, Resultado<String>)>,
+    contenedores: VecDeque<Resultado<Vec<ContainerSummary>>>,
+    /// Las eliminaciones se buscan por id, el orden entre categorías puede cambiar de una corrida a otra
+    eliminaciones: HashMap<String, VecDeque<Resultado<()>>>,
+    distintas: Vec<String>,
+}
+
+impl Reproduccion {
+    fn cargar(archivo: &Path, velocidad: f64) -> Result<Self, Box<dyn Error>> {
+        let lector = BufReader::new(File::open(archivo).map_err(|e| format!("No se pudo abrir {}: {}", archivo.display(), e))?);
+        let mut lineas = lector.lines().enumerate();
+
+        let encabezado: Encabezado = match lineas.next() {
+            Some((_, linea)) => serde_json::from_str(&linea?).map_err(|e| format!("Encabezado inválido: {}", e))?,
+            None => return Err(format!("{} está vacío", archivo.display()).into()),
+        };
+        if encabezado.version != VERSION {
+            return Err(format!("Versión de grabación {} no soportada, se esperaba {}", encabezado.version, VERSION).into());
+        }
+        let inicio = DateTime::parse_from_rfc3339(&encabezado.inicio)
+            .map_err(|e| format!("Inicio '{}' inválido en el encabezado: {}", encabezado.inicio, e))?
+            .with_timezone(&Utc);
+
+        let mut entradas = Vec::new();
+        let mut cursor = Cursor::default();
+        for (numero, linea) in lineas {
+            let linea = linea?;
+            if linea.trim().is_empty() {
+                continue;
+            }
+            let entrada: Entrada =
+                serde_json::from_str(&linea).map_err(|e| format!("Línea {} inválida: {}", numero + 1, e))?;
+            if entrada.monitor {
+                match &entrada.lectura {
+                    Lectura::Sysinfo { resultado } => cursor.sysinfo.push_back((entrada.ms, resultado.clone())),
+                    Lectura::Contenedores { resultado } => cursor.contenedores.push_back(resultado.clone()),
+                    Lectura::Eliminar { id, resultado } => {
+                        cursor.eliminaciones.entry(id.clone()).or_default().push_back(resultado.clone())
+                    }
+                }
+            }
+            entradas.push(entrada);
+        }
+        if cursor.sysinfo.is_empty() {
+            return Err(format!("{} no tiene ningún ciclo del monitor", archivo.display()).into());
+        }
+
+        log::info!(
+            "Reproduciendo {} ({} ciclos grabados el {}, {} en el original) a velocidad {}",
+            archivo.display(),
+            cursor.sysinfo.len(),
+            encabezado.inicio,
+            encabezado.sysinfo,
+            velocidad
+        );
+        Ok(Reproduccion { velocidad, inicio, entradas, estado: Mutex::new(cursor) })
+    }
+
+    fn ahora(&self) -> DateTime<Utc> {
+        let ms = self.estado.lock().unwrap().ms;
+        self.inicio + chrono::Duration::milliseconds(ms as i64)
+    }
+
+    fn sysinfo(&self, monitor: bool) -> Result<String, String> {
+        let mut cursor = self.estado.lock().unwrap();
+        let resultado = if monitor {
+            let (ms, resultado) = cursor.sysinfo.pop_front().ok_or("La grabación no tiene más muestras")?;
+            cursor.ms = ms;
+            cursor.ciclos += 1;
+            resultado
+        } else {
+            self.ultima(cursor.ms, |lectura| match lectura {
+                Lectura::Sysinfo { resultado } => Some(resultado.clone()),
+                _ => None,
+            })
+            .ok_or("La grabación no tiene lecturas de sysinfo")?
+        };
+        match resultado {
+            Resultado::Ok(contenido) => Ok(contenido),
+            Resultado::Error(e) => Err(e),
+        }
+    }
+
+    fn contenedores(&self, monitor: bool) -> Result<Vec<ContainerSummary>, String> {
+        let mut cursor = self.estado.lock().unwrap();
+        let resultado = if monitor {
+            cursor.contenedores.pop_front()
+        } else {
+            self.ultima(cursor.ms, |lectura| match lectura {
+                Lectura::Contenedores { resultado } => Some(resultado.clone()),
+                _ => None,
+            })
+        };
+        match resultado.ok_or("La grabación no tiene más listas de contenedores")? {
+            Resultado::Ok(contenedores) => Ok(contenedores),
+            Resultado::Error(e) => Err(e),
+        }
+    }
+
+    fn eliminar(&self, id: &str, monitor: bool) -> Result<(), String> {
+        if !monitor {
+            return Ok(());
+        }
+        let mut cursor = self.estado.lock().unwrap();
+        match cursor.eliminaciones.get_mut(id).and_then(|pendientes| pendientes.pop_front()) {
+            Some(Resultado::Ok(())) => Ok(()),
+            Some(Resultado::Error(e)) => Err(e),
+            None => {
+                log::warn!("El monitor eliminó {} y en la grabación no se eliminó", id);
+                cursor.distintas.push(id.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    /// La última lectura que cumple `filtro` hasta `ms`, o la primera si todavía no hay ninguna
+    fn ultima<T>(&self, ms: u64, filtro: impl Fn(&Lectura) -> Option<T>) -> Option<T> {
+        let mut candidatas = self.entradas.iter().filter_map(|e| filtro(&e.lectura).map(|valor| (e.ms, valor)));
+        let primera = candidatas.next()?;
+        Some(candidatas.take_while(|(ms_entrada, _)| *ms_entrada <= ms).last().unwrap_or(primera).1)
+    }
+
+    fn espera_siguiente(&self) -> Option<Duration> {
+        let cursor = self.estado.lock().unwrap();
+        let (siguiente, _) = cursor.sysinfo.front()?;
+        if self.velocidad == 0.0 {
+            return Some(Duration::ZERO);
+        }
+        let segundos = siguiente.saturating_sub(cursor.ms) as f64 / 1000.0 / self.velocidad;
+        Some(Duration::try_from_secs_f64(segundos).unwrap_or(Duration::MAX))
+    }
+
+    fn resumen(&self) -> String {
+        let cursor = self.estado.lock().unwrap();
+        let sin_hacer: Vec<&String> =
+            cursor.eliminaciones.iter().filter(|(_, pendientes)| !pendientes.is_empty()).map(|(id, _)| id).collect();
+        if cursor.distintas.is_empty() && sin_hacer.is_empty() {
+            format!("Reproducción terminada: {} ciclos, mismas decisiones que en la grabación", cursor.ciclos)
+        } else {
+            format!(
+                "Reproducción terminada: {} ciclos, {} eliminaciones que no estaban en la grabación {:?} y {} que faltaron {:?}",
+                cursor.ciclos,
+                cursor.distintas.len(),
+                cursor.distintas,
+                sin_hacer.len(),
+                sin_hacer
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contenedor(id: &str) -> ContainerSummary {
+        ContainerSummary { id: Some(id.to_string()), names: Some(vec![format!("/stress_cpu_{}", id)]), ..Default::default() }
+    }
+
+    #[test]
+    fn lo_grabado_se_reproduce_igual() {
+        let dir = tempfile::tempdir().unwrap();
+        let grabadora = Grabadora::crear(dir.path(), "/proc/sysinfo_prueba").unwrap();
+        let fallo: Result<String, String> = Err("sin módulo".to_string());
+        grabadora.registrar(true, Lectura::Sysinfo { resultado: (&Ok::<_, String>("muestra 1".to_string())).into() });
+        grabadora.registrar(true, Lectura::Contenedores { resultado: Resultado::Ok(vec![contenedor("a"), contenedor("b")]) });
+        grabadora.registrar(true, Lectura::Eliminar { id: "a".to_string(), resultado: Resultado::Ok(()) });
+        grabadora.registrar(true, Lectura::Eliminar { id: "b".to_string(), resultado: Resultado::Error("ya no existe".to_string()) });
+        grabadora.registrar(false, Lectura::Sysinfo { resultado: (&Ok::<_, String>("consulta suelta".to_string())).into() });
+        std::thread::sleep(Duration::from_millis(20));
+        grabadora.registrar(true, Lectura::Sysinfo { resultado: (&fallo).into() });
+        grabadora.registrar(true, Lectura::Contenedores { resultado: Resultado::Ok(Vec::new()) });
+        let archivo = grabadora.archivo.clone();
+        drop(grabadora);
+
+        let reproduccion = Reproduccion::cargar(&archivo, 0.0).unwrap();
+        let inicio = reproduccion.ahora();
+
+        // Primer ciclo
+        assert_eq!(reproduccion.sysinfo(true).unwrap(), "muestra 1");
+        let ids: Vec<_> = reproduccion.contenedores(true).unwrap().into_iter().filter_map(|c| c.id).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        // Las consultas sueltas no avanzan la reproducción
+        assert_eq!(reproduccion.sysinfo(false).unwrap(), "consulta suelta");
+        // En otro orden da lo mismo, se buscan por id
+        assert_eq!(reproduccion.eliminar("b", true).unwrap_err(), "ya no existe");
+        assert!(reproduccion.eliminar("a", true).is_ok());
+        assert_eq!(reproduccion.espera_siguiente(), Some(Duration::ZERO));
+
+        // Segundo ciclo, el error también se reproduce y el reloj avanza lo que pasó al grabar
+        assert_eq!(reproduccion.sysinfo(true).unwrap_err(), "sin módulo");
+        assert!(reproduccion.contenedores(true).unwrap().is_empty());
+        assert!(reproduccion.ahora() - inicio >= chrono::Duration::milliseconds(20));
+        assert_eq!(reproduccion.espera_siguiente(), None);
+        assert!(reproduccion.resumen().contains("2 ciclos, mismas decisiones"), "{}", reproduccion.resumen());
+    }
+
+    #[test]
+    fn eliminaciones_distintas_quedan_en_el_resumen() {
+        let dir = tempfile::tempdir().unwrap();
+        let grabadora = Grabadora::crear(dir.path(), "/proc/sysinfo_prueba").unwrap();
+        grabadora.registrar(true, Lectura::Sysinfo { resultado: Resultado::Ok("{}".to_string()) });
+        grabadora.registrar(true, Lectura::Eliminar { id: "a".to_string(), resultado: Resultado::Ok(()) });
+        let reproduccion = Reproduccion::cargar(&grabadora.archivo, 1.0).unwrap();
+
+        reproduccion.sysinfo(true).unwrap();
+        reproduccion.eliminar("z", true).unwrap();
+        let resumen = reproduccion.resumen();
+        assert!(resumen.contains("[\"z\"]") && resumen.contains("[\"a\"]"), "{}", resumen);
+    }
+
+    #[test]
+    fn velocidad_fuera_de_rango() {
+        for velocidad in [-1.0, 1e-300, f64::NAN, f64::INFINITY] {
+            let modo = Modo::Reproducir { archivo: PathBuf::from("/nonexistent.ndjson"), velocidad };
+            let error = instalar(modo, "/proc/sysinfo").unwrap_err();
+            assert!(error.to_string().contains("--velocidad"), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use chrono::{DateTime, Duration, Utc};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::metricas;
+
+type Resultado<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+const ANCHO: u32 = 1024;
+const ALTO: u32 = 480;
+
+#[derive(Debug, Deserialize)]
+struct RegistroConteo {
+    timestamp: String,
+    categorias: HashMap<String, usize>,
+}
+
+struct Serie {
+    nombre: String,
+    puntos: Vec<(DateTime<Utc>, f64)>,
+}
+
+/// Genera las gráficas de CPU, RAM y contenedores por categoría en SVG y PNG.
+/// Devuelve los nombres de los archivos escritos dentro de `dir_salida`.
+pub fn generar_graficas(cpu_json: &str, ram_json: &str, conteo_json: &str, dir_salida: &Path) -> Resultado<Vec<String>> {
+    fs::create_dir_all(dir_salida)?;
+
+    let serie_cpu = Serie {
+        nombre: "CPU".to_string(),
+        puntos: metricas::leer_cpu(cpu_json)?,
+    };
+
+    // La RAM se guarda en KB, se grafica en MB para que el eje sea legible
+    let serie_ram = Serie {
+        nombre: "RAM".to_string(),
+        puntos: metricas::leer_ram(ram_json)?
+            .into_iter()
+            .map(|(t, kb)| (t, kb / 1024.0))
+            .collect(),
+    };
+
+    let conteos: Vec<RegistroConteo> = metricas::leer_registros(conteo_json)?;
+    let mut por_categoria: BTreeMap<String, Vec<(DateTime<Utc>, f64)>> = BTreeMap::new();
+    for registro in &conteos {
+        if let Some(t) = metricas::parsear_fecha(&registro.timestamp) {
+            for (categoria, cantidad) in &registro.categorias {
+                por_categoria.entry(categoria.clone()).or_default().push((t, *cantidad as f64));
+            }
+        }
+    }
+    let series_conteo: Vec<Serie> = por_categoria
+        .into_iter()
+        .map(|(nombre, puntos)| Serie { nombre, puntos })
+        .collect();
+
+    let graficas = [
+        ("cpu", "Uso de CPU", "CPU (%)", vec![serie_cpu]),
+        ("ram", "RAM ocupada", "RAM (MB)", vec![serie_ram]),
+        ("contenedores", "Contenedores por categoría", "Contenedores", series_conteo),
+    ];
+
+    let mut archivos = Vec::new();
+    for (nombre, titulo, etiqueta_y, series) in graficas.iter() {
+        if series.iter().all(|s| s.puntos.is_empty()) {
+            log::warn!("No hay datos para la gráfica '{}', se omite", nombre);
+            continue;
+        }
+
+        let svg = format!("{}.svg", nombre);
+        dibujar(SVGBackend::new(&dir_salida.join(&svg), (ANCHO, ALTO)).into_drawing_area(), titulo, etiqueta_y, series)?;
+        archivos.push(svg);
+
+        let png = format!("{}.png", nombre);
+        dibujar(BitMapBackend::new(&dir_salida.join(&png), (ANCHO, ALTO)).into_drawing_area(), titulo, etiqueta_y, series)?;
+        archivos.push(png);
+    }
+
+    Ok(archivos)
+}
+
+fn dibujar<DB>(root: DrawingArea<DB, Shift>, titulo: &str, etiqueta_y: &str, series: &[Serie]) -> Resultado<()>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    let puntos = series.iter().flat_map(|s| s.puntos.iter());
+    let inicio = puntos.clone().map(|p| p.0).min().unwrap_or_else(Utc::now);
+    let mut fin = puntos.clone().map(|p| p.0).max().unwrap_or(inicio);
+    if fin <= inicio {
+        fin = inicio + Duration::seconds(1);
+    }
+    let max_y = puntos.map(|p| p.1).fold(0.0, f64::max).max(1.0) * 1.1;
+
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(titulo, ("sans-serif", 24))
+        .margin(15)
+        .x_label_area_size(40)
+        .y_label_area_size(70)
+        .build_cartesian_2d(inicio..fin, 0.0..max_y)?;
+
+    chart
+        .configure_mesh()
+        .x_labels(8)
+        .x_label_formatter(&|t| t.format("%H:%M:%S").to_string())
+        .y_desc(etiqueta_y)
+        .draw()?;
+
+    for (i, serie) in series.iter().enumerate() {
+        let color = Palette99::pick(i).to_rgba();
+        chart
+            .draw_series(LineSeries::new(serie.puntos.iter().cloned(), color.stroke_width(2)))?
+            .label(serie.nombre.as_str())
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2)));
+    }
+
+    if series.len() > 1 {
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+    }
+
+    root.present()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn genera_svg_y_png_de_cada_grafica() {
+        let dir = tempfile::tempdir().unwrap();
+        let archivo = |nombre: &str, contenido: &str| {
+            let ruta = dir.path().join(nombre);
+            fs::write(&ruta, contenido).unwrap();
+            ruta.display().to_string()
+        };
+        let cpu = archivo(
+            "cpu.json",
+            r#"[{"timestamp":"2024-05-10T12:00:00Z","cpu_usada":10},{"timestamp":"2024-05-10T12:00:10Z","cpu_usada":80}]"#,
+        );
+        let ram = archivo("ram.json", r#"[{"timestamp":"2024-05-10T12:00:00Z","ram_ocupada":2048}]"#);
+        let conteo = archivo(
+            "conteo.json",
+            r#"[{"timestamp":"2024-05-10T12:00:00Z","categorias":{"cpu":1,"ram":2}},{"timestamp":"no es fecha","categorias":{"io":1}}]"#,
+        );
+
+        let salida = dir.path().join("salida");
+        let archivos = generar_graficas(&cpu, &ram, &conteo, &salida).unwrap();
+        assert_eq!(archivos, vec!["cpu.svg", "cpu.png", "ram.svg", "ram.png", "contenedores.svg", "contenedores.png"]);
+        for archivo in &archivos {
+            assert!(fs::metadata(salida.join(archivo)).unwrap().len() > 0, "{}", archivo);
+        }
+        let svg = fs::read_to_string(salida.join("contenedores.svg")).unwrap();
+        assert!(svg.contains("Contenedores por categoría"));
+    }
+
+    #[test]
+    fn sin_datos_no_se_genera_nada() {
+        let dir = tempfile::tempdir().unwrap();
+        let no_existe = dir.path().join("no-existe.json").display().to_string();
+        let archivos = generar_graficas(&no_existe, &no_existe, &no_existe, dir.path()).unwrap();
+        assert!(archivos.is_empty());
+    }
+}
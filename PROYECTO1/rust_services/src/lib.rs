@@ -0,0 +1,250 @@
+//! Lo que comparten los subcomandos de `rust_services`: los tipos del módulo de kernel,
+//! la clasificación de contenedores de stress y la lógica de Docker.
+
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+use std::process::Command;
+use bollard::Docker;
+use std::collections::HashMap;
+use std::error::Error;
+
+mod alertas;
+mod api;
+mod auth;
+mod cierre;
+pub mod config;
+mod dashboard;
+mod escenarios;
+mod escritor_logs;
+mod eventos;
+pub mod gestor_crontab;
+pub mod grabacion;
+mod graficas;
+mod limite;
+mod logs;
+mod metricas;
+mod programador;
+mod reporte;
+mod salud;
+pub mod servidor;
+pub mod stress;
+mod tls;
+pub mod tui;
+pub mod volcado;
+
+use alertas::MotorAlertas;
+use dashboard::{ContenedorVivo, EstadoMonitor};
+use escritor_logs::EscritorLogs;
+use eventos::BusEventos;
+use reporte::Sesion;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct RequestLog {
+    timestamp: u64,
+    method: String,
+    path: String,
+    status: u16,
+    ip: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SystemInfo {
+    pub system: SystemStats,
+    pub containers: Vec<ContainerInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct SystemStats {
+    pub ram_total: u64,
+    pub ram_libre: u64,
+    pub ram_ocupada: u64,
+    pub cpu_usada: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerInfo {
+    pub pid: u32,
+    pub name: String,
+    pub cmdline: String,
+    pub memory_rss: u64,
+    pub memory_percent: u8,
+    pub virtual_memory: u64,
+    pub cpu_percent: u8,
+    pub disk_usage: u64,
+    pub io_read_bytes: u64,
+    pub io_write_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerLog {
+    pub timestamp: u64,  // Keep this as u64, but we'll convert when needed
+    pub category: String,
+    pub name: String,
+    pub action: String,
+}
+
+#[allow(dead_code)]
+struct AppState {
+    logger_container_id: String,
+    cierre: Arc<cierre::Cierre>,
+    docker: Arc<Docker>,
+    monitor: Arc<EstadoMonitor>,
+    eventos: Arc<BusEventos>,
+    alertas: Arc<MotorAlertas>,
+    sesion: Arc<Sesion>,
+    escritor_logs: Arc<EscritorLogs>,
+    auth: Arc<auth::ConfigAuth>,
+    salud: Arc<salud::EstadoSalud>,
+    limitador: Arc<limite::Limitador>,
+    programador: Arc<programador::Programador>,
+    escenarios: Arc<escenarios::Escenarios>,
+    protegidos: Arc<stress::Protegidos>,
+}
+
+impl AppState {
+    /// Arma el estado compartido de `serve` y `monitor` a partir de la configuración
+    fn new(config: config::Config, logger_container_id: String) -> Result<Self, Box<dyn Error>> {
+        let programador = programador::Programador::new(config.programador)
+            .map_err(|e| format!("Trabajos del programador inválidos: {}", e))?;
+        if !config.auth.habilitada() {
+            log::warn!("No hay tokens en [auth], la API queda abierta a cualquiera");
+        }
+
+        Ok(AppState {
+            logger_container_id,
+            cierre: Arc::new(cierre::Cierre::new()),
+            docker: Arc::new(Docker::connect_with_local_defaults()?),
+            monitor: Arc::new(EstadoMonitor::new()),
+            eventos: Arc::new(BusEventos::new()),
+            alertas: Arc::new(MotorAlertas::new(config.alertas)),
+            sesion: Arc::new(Sesion::new()),
+            // Los logs de peticiones se escriben en el volumen del contenedor logger
+            escritor_logs: Arc::new(EscritorLogs::iniciar(&config.logger.requests_log())),
+            auth: Arc::new(config.auth),
+            salud: Arc::new(salud::EstadoSalud::new()),
+            limitador: Arc::new(limite::Limitador::new(config.limites)),
+            programador: Arc::new(programador),
+            escenarios: Arc::new(escenarios::Escenarios::new(&config.escenarios)),
+            protegidos: Arc::new(stress::Protegidos::new()),
+        })
+    }
+
+    /// Contenedores que no se deben eliminar: los de un escenario en curso
+    /// y los de `POST /api/stress` mientras dure su stress
+    fn protegido(&self, id: &str) -> bool {
+        self.escenarios.protegido(id) || self.protegidos.contiene(id)
+    }
+}
+
+fn enviar_container_logs(logs: &[ContainerLog]) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // Al reproducir una grabación no hay contenedor logger
+    if grabacion::reproduciendo() {
+        return Ok(());
+    }
+    let logs_json = serde_json::to_string(logs)?;
+    Command::new("docker")
+        .args(["exec", &config::actual().logger.nombre, "sh", "-c", &format!("echo '{}' >> /logs/container_logs.json", logs_json)])
+        .output()?;
+    Ok(())
+}
+
+/// Deja solo el contenedor más nuevo de cada categoría, o ninguno con `todos`.
+/// Los `protegido` no se tocan ni cuentan. Devuelve los que se eliminaron.
+pub async fn limpiar_contenedores(
+    docker: &Docker,
+    todos: bool,
+    protegido: impl Fn(&str) -> bool,
+) -> Result<Vec<ContainerLog>, bollard::errors::Error> {
+    let containers = grabacion::contenedores(docker, false).await?;
+    let mut container_map: HashMap<String, Vec<(i64, String, String)>> = HashMap::new();
+    
+    for container in containers {
+        if let (Some(names), Some(id)) = (&container.names, &container.id) {
+            if protegido(id) {
+                continue;
+            }
+            for name in names {
+                if let Some(label) = clasificar_contenedor(name) {
+                    let name = name.trim_start_matches('/').to_string();
+                    container_map.entry(label).or_default().push((container.created.unwrap_or(0), id.clone(), name));
+                }
+            }
+        }
+    }
+    
+    let mut eliminados = Vec::new();
+    for (category, containers) in container_map.iter_mut() {
+        // El más nuevo queda al final
+        containers.sort();
+        if !todos {
+            containers.pop();
+        }
+        for (_, id, name) in containers.drain(..) {
+            match grabacion::eliminar(docker, &id, false).await {
+                Ok(_) => eliminados.push(ContainerLog {
+                    timestamp: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs(),
+                    category: category.clone(),
+                    name,
+                    action: "eliminado".to_string(),
+                }),
+                // Con auto_remove puede que ya se esté borrando solo
+                Err(e) => log::warn!("No se pudo eliminar el contenedor {}: {}", name, e),
+            }
+        }
+    }
+    Ok(eliminados)
+}
+
+pub fn clasificar_contenedor(name: &str) -> Option<String> {
+    if name.contains("stress_ram") {
+        Some("ram".to_string())
+    } else if name.contains("stress_cpu") {
+        Some("cpu".to_string())
+    } else if name.contains("stress_io") {
+        Some("io".to_string())
+    } else if name.contains("stress_disk") {
+        Some("disk".to_string())
+    } else {
+        None
+    }
+}
+
+// Los procesos del módulo de kernel no traen el nombre del contenedor,
+// se clasifican por los argumentos que recibe stress
+fn clasificar_proceso(cmdline: &str) -> Option<String> {
+    if cmdline.contains("--vm") {
+        Some("ram".to_string())
+    } else if cmdline.contains("--cpu") {
+        Some("cpu".to_string())
+    } else if cmdline.contains("--io") {
+        Some("io".to_string())
+    } else if cmdline.contains("--hdd") {
+        Some("disk".to_string())
+    } else {
+        None
+    }
+}
+
+fn enriquecer_contenedores(containers: Vec<ContainerInfo>) -> Vec<ContenedorVivo> {
+    containers
+        .into_iter()
+        .map(|info| {
+            let categoria = clasificar_contenedor(&info.name).or_else(|| clasificar_proceso(&info.cmdline));
+            ContenedorVivo { info, categoria }
+        })
+        .collect()
+}
+
+pub fn leer_sysinfo() -> Result<SystemInfo, Box<dyn Error>> {
+    let data = grabacion::sysinfo(&config::actual().rutas.sysinfo, false)?;
+    let sys_info: SystemInfo = serde_json::from_str(&data)?;
+    Ok(sys_info)
+}
+
+/// La muestra de cada ciclo del monitor, al reproducir una grabación es la que avanza al siguiente ciclo
+fn leer_sysinfo_monitor() -> Result<SystemInfo, Box<dyn Error>> {
+    let data = grabacion::sysinfo(&config::actual().rutas.sysinfo, true)?;
+    let sys_info: SystemInfo = serde_json::from_str(&data)?;
+    Ok(sys_info)
+}
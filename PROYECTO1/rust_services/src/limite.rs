@@ -0,0 +1,277 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::auth;
+use crate::AppState;
+
+// Cada tanto se borran las cubetas que ya se llenaron, son de clientes que no han vuelto
+const LIMPIAR_CON: usize = 10_000;
+
+/// Token bucket: se pueden hacer `rafaga` peticiones seguidas y se recuperan `por_segundo`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Presupuesto {
+    pub rafaga: f64,
+    pub por_segundo: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigLimites {
+    pub habilitado: bool,
+    /// GET y HEAD
+    pub lectura: Presupuesto,
+    /// Todo lo demás: control de contenedores, stress, gráficas
+    pub escritura: Presupuesto,
+    /// Tamaño máximo del body de una petición
+    pub max_body_bytes: usize,
+}
+
+impl Default for ConfigLimites {
+    fn default() -> Self {
+        ConfigLimites {
+            habilitado: true,
+            lectura: Presupuesto { rafaga: 60.0, por_segundo: 20.0 },
+            escritura: Presupuesto { rafaga: 10.0, por_segundo: 0.5 },
+            max_body_bytes: 16 * 1024,
+        }
+    }
+}
+
+impl Presupuesto {
+    fn validar(&self, clave: &str) -> Result<(), String> {
+        if !self.rafaga.is_finite() || self.rafaga < 1.0 {
+            return Err(format!("limites.{}.rafaga debe ser al menos 1", clave));
+        }
+        if !self.por_segundo.is_finite() || self.por_segundo <= 0.0 {
+            return Err(format!("limites.{}.por_segundo debe ser un número mayor que 0", clave));
+        }
+        Ok(())
+    }
+}
+
+impl ConfigLimites {
+    /// Con el límite deshabilitado los presupuestos no se usan
+    pub fn validar(&self) -> Result<(), String> {
+        if !self.habilitado {
+            return Ok(());
+        }
+        self.lectura.validar("lectura")?;
+        self.escritura.validar("escritura")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Tipo {
+    Lectura,
+    Escritura,
+}
+
+#[derive(Debug)]
+struct Cubeta {
+    fichas: f64,
+    actualizada: Instant,
+}
+
+impl Cubeta {
+    fn rellenar(&mut self, presupuesto: &Presupuesto, ahora: Instant) {
+        let transcurrido = ahora.duration_since(self.actualizada).as_secs_f64();
+        self.fichas = (self.fichas + transcurrido * presupuesto.por_segundo).min(presupuesto.rafaga);
+        self.actualizada = ahora;
+    }
+
+    /// Cuánto falta para tener una ficha
+    fn espera(&self, presupuesto: &Presupuesto) -> Duration {
+        if self.fichas >= 1.0 {
+            Duration::ZERO
+        } else {
+            // Con `por_segundo` muy chico la espera no cabe en un Duration
+            Duration::try_from_secs_f64((1.0 - self.fichas) / presupuesto.por_segundo).unwrap_or(Duration::MAX)
+        }
+    }
+}
+
+/// Cubetas por IP y por token, cada una con presupuesto separado para lectura y escritura
+pub struct Limitador {
+    config: ConfigLimites,
+    cubetas: Mutex<HashMap<(String, Tipo), Cubeta>>,
+}
+
+impl Limitador {
+    pub fn new(config: ConfigLimites) -> Self {
+        Limitador { config, cubetas: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn max_body_bytes(&self) -> usize {
+        self.config.max_body_bytes
+    }
+
+    /// Gasta una ficha de cada clave, o ninguna si a alguna no le alcanza.
+    /// Si no alcanza devuelve cuánto hay que esperar.
+    fn tomar(&self, claves: &[String], tipo: Tipo) -> Result<(), Duration> {
+        let presupuesto = match tipo {
+            Tipo::Lectura => self.config.lectura,
+            Tipo::Escritura => self.config.escritura,
+        };
+        let ahora = Instant::now();
+        let mut cubetas = self.cubetas.lock().unwrap();
+
+        if cubetas.len() > LIMPIAR_CON {
+            let (lectura, escritura) = (self.config.lectura, self.config.escritura);
+            cubetas.retain(|(_, tipo), cubeta| {
+                let presupuesto = if *tipo == Tipo::Lectura { lectura } else { escritura };
+                cubeta.rellenar(&presupuesto, ahora);
+                cubeta.fichas < presupuesto.rafaga
+            });
+        }
+
+        let mut espera = Duration::ZERO;
+        for clave in claves {
+            let cubeta = cubetas
+                .entry((clave.clone(), tipo))
+                .or_insert(Cubeta { fichas: presupuesto.rafaga, actualizada: ahora });
+            cubeta.rellenar(&presupuesto, ahora);
+            espera = espera.max(cubeta.espera(&presupuesto));
+        }
+        if espera > Duration::ZERO {
+            return Err(espera);
+        }
+
+        for clave in claves {
+            if let Some(cubeta) = cubetas.get_mut(&(clave.clone(), tipo)) {
+                cubeta.fichas -= 1.0;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Segundos para `Retry-After`, redondeados hacia arriba y nunca 0
+fn retry_after(espera: Duration) -> u64 {
+    espera.as_secs_f64().ceil().max(1.0) as u64
+}
+
+/// Middleware de límite de peticiones. Va fuera del de autenticación, así los clientes sin
+/// token también se limitan, y dentro del que registra peticiones para que los 429 queden en `requests.log`.
+pub async fn limitar(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let limitador = match req.app_data::<web::Data<AppState>>() {
+        Some(data) if data.limitador.config.habilitado => data.limitador.clone(),
+        _ => return next.call(req).await.map(ServiceResponse::map_into_left_body),
+    };
+
+    // Las sondas de liveness y readiness no cuentan
+    if req.path() == "/healthz" || req.path() == "/readyz" {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+
+    let tipo = if req.method() == Method::GET || req.method() == Method::HEAD {
+        Tipo::Lectura
+    } else {
+        Tipo::Escritura
+    };
+
+    let mut claves = vec![format!("ip:{}", req.connection_info().peer_addr().unwrap_or("unknown"))];
+    if let Some(token) = auth::token_de(&req) {
+        claves.push(format!("token:{}", auth::sha256_hex(&token)));
+    }
+
+    match limitador.tomar(&claves, tipo) {
+        Ok(()) => next.call(req).await.map(ServiceResponse::map_into_left_body),
+        Err(espera) => {
+            let segundos = retry_after(espera);
+            log::warn!("{} {} rechazada por límite de peticiones ({})", req.method(), req.path(), claves.join(", "));
+            let respuesta = HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", segundos.to_string()))
+                .json(json!({ "error": format!("Demasiadas peticiones, intente de nuevo en {} s", segundos) }));
+            Ok(req.into_response(respuesta).map_into_right_body())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limitador(rafaga: f64, por_segundo: f64) -> Limitador {
+        let presupuesto = Presupuesto { rafaga, por_segundo };
+        Limitador::new(ConfigLimites { lectura: presupuesto, escritura: presupuesto, ..ConfigLimites::default() })
+    }
+
+    #[test]
+    fn la_rafaga_se_agota_y_se_rellena() {
+        let limitador = limitador(3.0, 2.0);
+        let claves = vec!["ip:1".to_string()];
+        for _ in 0..3 {
+            assert!(limitador.tomar(&claves, Tipo::Lectura).is_ok());
+        }
+        let espera = limitador.tomar(&claves, Tipo::Lectura).unwrap_err();
+        assert!(espera > Duration::from_millis(400) && espera <= Duration::from_millis(500), "{:?}", espera);
+        // Lectura y escritura llevan cubetas separadas
+        assert!(limitador.tomar(&claves, Tipo::Escritura).is_ok());
+
+        // Medio segundo después se recuperó una ficha
+        let mut cubetas = limitador.cubetas.lock().unwrap();
+        let cubeta = cubetas.get_mut(&("ip:1".to_string(), Tipo::Lectura)).unwrap();
+        cubeta.actualizada -= Duration::from_millis(500);
+        drop(cubetas);
+        assert!(limitador.tomar(&claves, Tipo::Lectura).is_ok());
+        assert!(limitador.tomar(&claves, Tipo::Lectura).is_err());
+    }
+
+    #[test]
+    fn rellenar_no_pasa_de_la_rafaga() {
+        let presupuesto = Presupuesto { rafaga: 5.0, por_segundo: 10.0 };
+        let ahora = Instant::now();
+        let mut cubeta = Cubeta { fichas: 0.0, actualizada: ahora - Duration::from_secs(60) };
+        cubeta.rellenar(&presupuesto, ahora);
+        assert_eq!(cubeta.fichas, 5.0);
+    }
+
+    #[test]
+    fn sin_fichas_en_alguna_clave_no_se_gasta_ninguna() {
+        let limitador = limitador(1.0, 1.0);
+        let ip = vec!["ip:1".to_string()];
+        let ambas = vec!["ip:1".to_string(), "token:abc".to_string()];
+        assert!(limitador.tomar(&ip, Tipo::Lectura).is_ok());
+        assert!(limitador.tomar(&ambas, Tipo::Lectura).is_err());
+        let cubetas = limitador.cubetas.lock().unwrap();
+        assert_eq!(cubetas[&("token:abc".to_string(), Tipo::Lectura)].fichas, 1.0);
+    }
+
+    #[test]
+    fn retry_after_redondea_hacia_arriba() {
+        assert_eq!(retry_after(Duration::from_millis(1)), 1);
+        assert_eq!(retry_after(Duration::from_millis(1500)), 2);
+        assert_eq!(retry_after(Duration::from_secs(2)), 2);
+        assert_eq!(retry_after(Duration::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn por_segundo_diminuto_no_entra_en_panico() {
+        let cubeta = Cubeta { fichas: 0.0, actualizada: Instant::now() };
+        let espera = cubeta.espera(&Presupuesto { rafaga: 1.0, por_segundo: 1e-300 });
+        assert_eq!(espera, Duration::MAX);
+    }
+
+    #[test]
+    fn validar_presupuestos() {
+        assert!(ConfigLimites::default().validar().is_ok());
+        let mut config = ConfigLimites::default();
+        config.lectura.rafaga = 0.5;
+        assert!(config.validar().unwrap_err().contains("limites.lectura.rafaga"));
+        config.lectura.rafaga = 10.0;
+        for por_segundo in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            config.escritura.por_segundo = por_segundo;
+            assert!(config.validar().unwrap_err().contains("limites.escritura.por_segundo"));
+        }
+        config.habilitado = false;
+        assert!(config.validar().is_ok());
+    }
+}
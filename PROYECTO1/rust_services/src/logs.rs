@@ -0,0 +1,361 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, Responder};
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::time::Duration;
+
+use crate::metricas::parsear_instante;
+use crate::{config, RequestLog};
+
+const LIMITE_DEFECTO: usize = 100;
+const LIMITE_MAX: usize = 1000;
+
+// Con follow=true se revisa el archivo cada segundo, y si no hay nada nuevo
+// se manda un comentario cada 15 para notar cuando el cliente se desconecta
+const INTERVALO_FOLLOW: Duration = Duration::from_secs(1);
+const ESPERAS_PING: u32 = 15;
+
+/// Query de `GET /logs`.
+/// `cursor` es el `siguiente_cursor` de la página anterior, `from` y `to` aceptan RFC3339 o segundos unix.
+/// Ej: `/logs?method=POST&path=/api&status_min=400&status_max=499&from=2025-03-14T23:00:00Z&limite=50`
+#[derive(Debug, Deserialize)]
+pub struct ConsultaLogs {
+    method: Option<String>,
+    path: Option<String>,
+    status_min: Option<u16>,
+    status_max: Option<u16>,
+    ip: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    cursor: Option<u64>,
+    limite: Option<usize>,
+    #[serde(default)]
+    follow: bool,
+}
+
+/// Filtro ya validado
+#[derive(Debug, Clone)]
+struct FiltroLogs {
+    method: Option<String>,
+    path: Option<String>,
+    status_min: u16,
+    status_max: u16,
+    ip: Option<String>,
+    desde: Option<i64>,
+    hasta: Option<i64>,
+}
+
+impl FiltroLogs {
+    fn desde_consulta(consulta: &ConsultaLogs) -> Result<Self, String> {
+        let status_min = consulta.status_min.unwrap_or(0);
+        let status_max = consulta.status_max.unwrap_or(u16::MAX);
+        if status_min > status_max {
+            return Err("status_min no puede ser mayor que status_max".to_string());
+        }
+
+        let desde = match consulta.from.as_deref() {
+            Some(valor) => Some(parsear_instante(valor).ok_or("from no es una fecha válida")?),
+            None => None,
+        };
+        let hasta = match consulta.to.as_deref() {
+            Some(valor) => Some(parsear_instante(valor).ok_or("to no es una fecha válida")?),
+            None => None,
+        };
+        if let (Some(desde), Some(hasta)) = (desde, hasta)
+            && desde > hasta
+        {
+            return Err("from debe ser anterior a to".to_string());
+        }
+
+        Ok(FiltroLogs {
+            method: consulta.method.as_ref().map(|m| m.to_uppercase()),
+            path: consulta.path.clone(),
+            status_min,
+            status_max,
+            ip: consulta.ip.clone(),
+            desde,
+            hasta,
+        })
+    }
+
+    fn acepta(&self, log: &RequestLog) -> bool {
+        let timestamp = log.timestamp as i64;
+        self.method.as_ref().is_none_or(|m| &log.method == m)
+            && self.path.as_ref().is_none_or(|p| log.path.starts_with(p.as_str()))
+            && (self.status_min..=self.status_max).contains(&log.status)
+            && self.ip.as_ref().is_none_or(|ip| &log.ip == ip)
+            && self.desde.is_none_or(|desde| timestamp >= desde)
+            && self.hasta.is_none_or(|hasta| timestamp <= hasta)
+    }
+}
+
+struct Lectura {
+    logs: Vec<RequestLog>,
+    // Offset en bytes donde sigue la próxima lectura
+    offset: u64,
+    fin: bool,
+}
+
+/// Lee `requests.log` (un `RequestLog` en JSON por línea) desde el byte `desde`.
+/// Para en cuanto junta `limite` registros; una última línea sin `\n` todavía se
+/// está escribiendo y se deja para la siguiente lectura.
+fn leer_desde(file_path: &str, desde: u64, filtro: &FiltroLogs, limite: usize) -> io::Result<Lectura> {
+    let mut file = File::open(file_path)?;
+    if desde > file.metadata()?.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "el cursor está fuera del archivo de logs"));
+    }
+    file.seek(SeekFrom::Start(desde))?;
+
+    let mut reader = BufReader::new(file);
+    let mut lectura = Lectura { logs: Vec::new(), offset: desde, fin: false };
+    let mut linea = String::new();
+
+    while lectura.logs.len() < limite {
+        linea.clear();
+        let leidos = reader.read_line(&mut linea)?;
+        if leidos == 0 || !linea.ends_with('\n') {
+            lectura.fin = true;
+            break;
+        }
+        lectura.offset += leidos as u64;
+
+        match serde_json::from_str::<RequestLog>(linea.trim()) {
+            Ok(log) if filtro.acepta(&log) => lectura.logs.push(log),
+            Ok(_) => {}
+            Err(e) => log::warn!("Línea inválida en {}: {}", file_path, e),
+        }
+    }
+
+    Ok(lectura)
+}
+
+// El archivo que escribe el contenedor logger en su volumen
+fn archivo_logs() -> String {
+    config::actual().logger.requests_log()
+}
+
+fn error_json(status: StatusCode, mensaje: String) -> HttpResponse {
+    HttpResponse::build(status).json(json!({ "error": mensaje }))
+}
+
+fn error_lectura(e: io::Error) -> HttpResponse {
+    match e.kind() {
+        io::ErrorKind::NotFound => error_json(
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("No existe {}, el contenedor logger no ha escrito logs", archivo_logs()),
+        ),
+        io::ErrorKind::InvalidInput => error_json(StatusCode::BAD_REQUEST, e.to_string()),
+        _ => error_json(StatusCode::INTERNAL_SERVER_ERROR, format!("No se pudieron leer los logs: {}", e)),
+    }
+}
+
+/// `GET /logs`, con `follow=true` se quedan llegando los logs nuevos por Server-Sent Events
+pub async fn get_logs(consulta: web::Query<ConsultaLogs>) -> impl Responder {
+    let consulta = consulta.into_inner();
+    let filtro = match FiltroLogs::desde_consulta(&consulta) {
+        Ok(filtro) => filtro,
+        Err(mensaje) => return error_json(StatusCode::BAD_REQUEST, mensaje),
+    };
+    let limite = consulta.limite.unwrap_or(LIMITE_DEFECTO).clamp(1, LIMITE_MAX);
+
+    if consulta.follow {
+        return seguir_logs(filtro, consulta.cursor).await;
+    }
+
+    let cursor = consulta.cursor.unwrap_or(0);
+    let lectura = match web::block(move || leer_desde(&archivo_logs(), cursor, &filtro, limite)).await {
+        Ok(Ok(lectura)) => lectura,
+        Ok(Err(e)) => return error_lectura(e),
+        Err(e) => return error_json(StatusCode::INTERNAL_SERVER_ERROR, format!("No se pudieron leer los logs: {}", e)),
+    };
+
+    HttpResponse::Ok().json(json!({
+        "logs": lectura.logs,
+        "siguiente_cursor": if lectura.fin { None } else { Some(lectura.offset) },
+        // Con este cursor y follow=true se sigue desde donde terminó la lectura
+        "cursor_fin": lectura.offset,
+    }))
+}
+
+struct Seguimiento {
+    filtro: FiltroLogs,
+    offset: u64,
+    pendientes: VecDeque<RequestLog>,
+    esperas: u32,
+}
+
+/// Dónde empieza `follow=true`: sin cursor al final del archivo, como `tail -f`.
+/// Un cursor más allá del final es un error, no se vuelve a mandar todo el archivo.
+fn offset_inicial(file_path: &str, cursor: Option<u64>) -> io::Result<u64> {
+    let largo = match std::fs::metadata(file_path) {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+        Err(e) => return Err(e),
+    };
+    match cursor {
+        None => Ok(largo),
+        Some(cursor) if cursor > largo => {
+            Err(io::Error::new(io::ErrorKind::InvalidInput, "el cursor está fuera del archivo de logs"))
+        }
+        Some(cursor) => Ok(cursor),
+    }
+}
+
+async fn seguir_logs(filtro: FiltroLogs, cursor: Option<u64>) -> HttpResponse {
+    let offset = match offset_inicial(&archivo_logs(), cursor) {
+        Ok(offset) => offset,
+        Err(e) => return error_lectura(e),
+    };
+
+    let inicio = futures::stream::once(async { Ok::<_, actix_web::Error>(web::Bytes::from_static(b": conectado\n\n")) });
+    let estado = Seguimiento { filtro, offset, pendientes: VecDeque::new(), esperas: 0 };
+
+    let stream = futures::stream::unfold(estado, |mut estado| async move {
+        loop {
+            if let Some(log) = estado.pendientes.pop_front() {
+                let json = serde_json::to_string(&log).unwrap_or_default();
+                return Some((Ok::<_, actix_web::Error>(web::Bytes::from(format!("data: {}\n\n", json))), estado));
+            }
+
+            tokio::time::sleep(INTERVALO_FOLLOW).await;
+
+            let (offset, filtro) = (estado.offset, estado.filtro.clone());
+            match web::block(move || leer_desde(&archivo_logs(), offset, &filtro, LIMITE_MAX)).await {
+                Ok(Ok(lectura)) => {
+                    estado.offset = lectura.offset;
+                    estado.pendientes.extend(lectura.logs);
+                }
+                // El cursor ya se validó al empezar, si ahora queda fuera es que el archivo se recreó
+                // (por ejemplo al reiniciar el logger) y todo lo que tiene es nuevo
+                Ok(Err(e)) if e.kind() == io::ErrorKind::InvalidInput => estado.offset = 0,
+                Ok(Err(e)) if e.kind() == io::ErrorKind::NotFound => {}
+                Ok(Err(e)) => log::warn!("No se pudieron seguir los logs: {}", e),
+                Err(e) => log::warn!("No se pudieron seguir los logs: {}", e),
+            }
+
+            if estado.pendientes.is_empty() {
+                estado.esperas += 1;
+                if estado.esperas >= ESPERAS_PING {
+                    estado.esperas = 0;
+                    return Some((Ok(web::Bytes::from_static(b": ping\n\n")), estado));
+                }
+            } else {
+                estado.esperas = 0;
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(inicio.chain(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+
+    fn consulta(query: &str) -> ConsultaLogs {
+        web::Query::<ConsultaLogs>::from_query(query).unwrap().into_inner()
+    }
+
+    fn filtro(query: &str) -> FiltroLogs {
+        FiltroLogs::desde_consulta(&consulta(query)).unwrap()
+    }
+
+    fn log(timestamp: u64, method: &str, path: &str, status: u16) -> RequestLog {
+        RequestLog { timestamp, method: method.to_string(), path: path.to_string(), status, ip: "10.0.0.1".to_string() }
+    }
+
+    /// Un `requests.log` con `cantidad` GET y un POST al final
+    fn archivo(dir: &std::path::Path, cantidad: u64) -> String {
+        let ruta = dir.join("requests.log");
+        let mut file = fs::File::create(&ruta).unwrap();
+        for i in 0..cantidad {
+            writeln!(file, "{}", serde_json::to_string(&log(1000 + i, "GET", "/api/system", 200)).unwrap()).unwrap();
+        }
+        writeln!(file, "no es json").unwrap();
+        writeln!(file, "{}", serde_json::to_string(&log(2000, "POST", "/api/stress", 429)).unwrap()).unwrap();
+        ruta.display().to_string()
+    }
+
+    #[test]
+    fn filtro_invalido() {
+        let error = |query| FiltroLogs::desde_consulta(&consulta(query)).unwrap_err();
+        assert!(error("status_min=500&status_max=400").contains("status_min"));
+        assert!(error("from=ayer").contains("from"));
+        assert!(error("to=mañana").contains("to"));
+        assert!(error("from=2000&to=1000").contains("anterior"));
+    }
+
+    #[test]
+    fn filtro_acepta() {
+        let get = log(1500, "GET", "/api/containers", 200);
+        assert!(filtro("").acepta(&get));
+        assert!(filtro("method=get&path=/api&status_max=299&ip=10.0.0.1").acepta(&get));
+        assert!(!filtro("method=POST").acepta(&get));
+        assert!(!filtro("path=/logs").acepta(&get));
+        assert!(!filtro("status_min=400").acepta(&get));
+        assert!(!filtro("ip=10.0.0.2").acepta(&get));
+        assert!(filtro("from=1500&to=1500").acepta(&get));
+        assert!(!filtro("from=1501").acepta(&get));
+        assert!(!filtro("to=1970-01-01T00:24:59Z").acepta(&get));
+    }
+
+    #[test]
+    fn paginas_con_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let ruta = archivo(dir.path(), 5);
+
+        let primera = leer_desde(&ruta, 0, &filtro(""), 2).unwrap();
+        assert_eq!(primera.logs.iter().map(|l| l.timestamp).collect::<Vec<_>>(), vec![1000, 1001]);
+        assert!(!primera.fin);
+
+        // La línea inválida se salta y la última página llega al final
+        let resto = leer_desde(&ruta, primera.offset, &filtro(""), 100).unwrap();
+        assert_eq!(resto.logs.iter().map(|l| l.timestamp).collect::<Vec<_>>(), vec![1002, 1003, 1004, 2000]);
+        assert!(resto.fin);
+        assert_eq!(resto.offset, fs::metadata(&ruta).unwrap().len());
+
+        let post = leer_desde(&ruta, 0, &filtro("method=POST"), 100).unwrap();
+        assert_eq!(post.logs.len(), 1);
+    }
+
+    #[test]
+    fn una_linea_a_medias_queda_para_la_siguiente_lectura() {
+        let dir = tempfile::tempdir().unwrap();
+        let ruta = archivo(dir.path(), 1);
+        let largo = fs::metadata(&ruta).unwrap().len();
+        fs::OpenOptions::new().append(true).open(&ruta).unwrap().write_all(b"{\"timestamp\":3000,").unwrap();
+
+        let lectura = leer_desde(&ruta, 0, &filtro(""), 100).unwrap();
+        assert_eq!(lectura.logs.len(), 2);
+        assert_eq!(lectura.offset, largo);
+        assert!(lectura.fin);
+    }
+
+    #[test]
+    fn cursor_fuera_del_archivo() {
+        let dir = tempfile::tempdir().unwrap();
+        let ruta = archivo(dir.path(), 1);
+        let largo = fs::metadata(&ruta).unwrap().len();
+
+        let error = leer_desde(&ruta, largo + 1, &filtro(""), 10).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        // En follow también se rechaza en vez de volver a empezar desde 0
+        assert_eq!(offset_inicial(&ruta, None).unwrap(), largo);
+        assert_eq!(offset_inicial(&ruta, Some(largo)).unwrap(), largo);
+        assert_eq!(offset_inicial(&ruta, Some(largo + 1)).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        let no_existe = dir.path().join("no-existe.log").display().to_string();
+        assert_eq!(offset_inicial(&no_existe, None).unwrap(), 0);
+        assert_eq!(offset_inicial(&no_existe, Some(10)).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -1,495 +1,252 @@
-use actix_web::{web, App, HttpServer, HttpResponse, Responder, middleware, HttpRequest};
-use serde::{Serialize, Deserialize};
-use std::sync::Arc;
-use std::process::Command;
-use std::time::{SystemTime, UNIX_EPOCH};
-use std::sync::atomic::AtomicBool;
 use bollard::Docker;
-use bollard::container::{ListContainersOptions, RemoveContainerOptions};
-use std::collections::HashMap;
-use tokio::time::{sleep, Duration};
-use std::fs;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
 use std::error::Error;
-use ctrlc;
-use serde_json::json;
-use std::fs::OpenOptions;
-use std::io::{Read, Write};
-use std::path::Path;
-use actix_files::Files;  // Importar actix_files
 
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-struct RequestLog {
-    timestamp: u64,
-    method: String,
-    path: String,
-    status: u16,
-    ip: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct SystemInfo {
-    system: SystemStats,
-    containers: Vec<ContainerInfo>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-struct SystemStats {
-    ram_total: u64,
-    ram_libre: u64,
-    ram_ocupada: u64,
-    cpu_usada: u8,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct ContainerInfo {
-    pid: u32,
-    name: String,
-    cmdline: String,
-    memory_rss: u64,
-    memory_percent: u8,
-    virtual_memory: u64,
-    cpu_percent: u8,
-    disk_usage: u64,
-    io_read_bytes: u64,
-    io_write_bytes: u64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct ContainerLog {
-    timestamp: u64,  // Keep this as u64, but we'll convert when needed
-    category: String,
-    name: String,
-    action: String,
-}
-
-#[allow(dead_code)]
-struct AppState {
-    logger_container_id: String,
-    shutdown_flag: Arc<AtomicBool>,
-    docker: Arc<Docker>,
-}
-
-async fn index(req: HttpRequest) -> impl Responder {
-    let ip = req.connection_info().peer_addr().unwrap_or("unknown").to_string();
-    
-    let log = RequestLog {
-        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
-        method: req.method().to_string(),
-        path: req.path().to_string(),
-        status: 200,
-        ip,
-    };
-    
-    log::info!("Request recibida: {:?}", log);
-    HttpResponse::Ok().body("¡Servicio funcionando!")
-}
-
-fn start_logger_container() -> String {
-    let output = Command::new("docker")
-        .args(&["run", "-d", "--name", "http_request_logger", "-v", "/tmp/http_logs:/logs", "alpine", "sh", "-c", "touch /logs/requests.log && touch /logs/container_logs.json && tail -f /dev/null"])
-        .output()
-        .expect("No se pudo crear el contenedor logger");
-    
-    String::from_utf8_lossy(&output.stdout).trim().to_string()
-}
-
-async fn gestionar_contenedores(docker: &Docker, logger_container_id: &str) {
-    loop {
-        let _ = gestionar_contenedores_por_categoria(docker, logger_container_id).await;
-        sleep(Duration::from_secs(10)).await;
+use rust_services::config::{self, Sobrescritura};
+use rust_services::gestor_crontab::{Cambio, EstadoBloque, GestorCrontab};
+use rust_services::grabacion::{self, Modo};
+use rust_services::volcado::{self, Formato, Umbrales};
+use rust_services::{limpiar_contenedores, servidor, stress, tui};
+
+/// Monitor de contenedores de stress con el módulo de kernel sysinfo
+#[derive(Debug, Parser)]
+#[command(name = "rust_services", version)]
+struct Cli {
+    /// Sin subcomando se corre `serve`
+    #[command(subcommand)]
+    comando: Option<Comando>,
+    /// Archivo de configuración, por defecto MONITOR_CONFIG o config.toml
+    #[arg(long, global = true, value_name = "ARCHIVO")]
+    config: Option<PathBuf>,
+    /// Cambia un valor de la configuración, se puede repetir: --set monitor.intervalo_segundos=5
+    #[arg(long = "set", global = true, value_name = "CLAVE=VALOR")]
+    sobrescrituras: Vec<String>,
+    /// Igual que --set servidor.puerto=N
+    #[arg(long, global = true)]
+    puerto: Option<u16>,
+    /// Igual que --set rutas.sysinfo=ARCHIVO
+    #[arg(long, global = true, value_name = "ARCHIVO")]
+    sysinfo: Option<String>,
+}
+
+impl Cli {
+    /// Carga la configuración en capas con lo que vino por línea de comandos encima
+    fn cargar_config(&self) -> Result<(config::Config, config::Origenes), String> {
+        let mut sobrescrituras =
+            self.sobrescrituras.iter().map(|texto| Sobrescritura::desde_set(texto)).collect::<Result<Vec<_>, _>>()?;
+        if let Some(puerto) = self.puerto {
+            sobrescrituras.push(Sobrescritura::new("servidor.puerto", i64::from(puerto), "--puerto"));
+        }
+        if let Some(sysinfo) = &self.sysinfo {
+            sobrescrituras.push(Sobrescritura::new("rutas.sysinfo", sysinfo.as_str(), "--sysinfo"));
+        }
+        config::cargar(self.config.as_deref(), &sobrescrituras)
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum Comando {
+    /// Servidor HTTP con el monitor, el programador y el crontab
+    Serve {
+        #[command(flatten)]
+        grabacion: OpcionesGrabacion,
+    },
+    /// Solo el ciclo del monitor en la consola, sin servidor HTTP
+    Monitor {
+        /// Hace un solo ciclo y termina
+        #[arg(long)]
+        una_vez: bool,
+        #[command(flatten)]
+        grabacion: OpcionesGrabacion,
+    },
+    /// Deja el contenedor de stress más nuevo de cada categoría
+    Cleanup {
+        /// Elimina todos los contenedores de stress
+        #[arg(long)]
+        todos: bool,
+    },
+    /// Administra el bloque de script.sh en el crontab
+    Cron {
+        #[command(subcommand)]
+        accion: AccionCron,
+    },
+    /// Muestra lo que reporta el módulo de kernel
+    Dump {
+        #[arg(long, value_enum, default_value_t = Formato::Table)]
+        format: Formato,
+        /// Campos de cada proceso separados por coma, sin ninguno salen todos. No va con --watch,
+        /// que imprime cambios y no procesos.
+        #[arg(long, value_delimiter = ',', conflicts_with = "watch")]
+        campos: Vec<String>,
+        /// Toma una muestra cada N segundos e imprime solo lo que cambió
+        #[arg(long, value_name = "N")]
+        watch: Option<u64>,
+        /// Con --watch, puntos de CPU que tiene que subir o bajar un proceso para reportarlo
+        #[arg(long, default_value_t = 10, requires = "watch")]
+        umbral_cpu: u8,
+        /// Con --watch, porcentaje que tiene que cambiar el RSS de un proceso para reportarlo
+        #[arg(long, default_value_t = 20, requires = "watch")]
+        umbral_rss: u64,
+    },
+    /// Vista de pantalla completa al estilo de `top`, con teclas para detener o matar contenedores
+    Top {
+        /// Segundos entre cada lectura
+        #[arg(long, default_value_t = 2)]
+        intervalo: u64,
+    },
+    /// Crea contenedores de stress de categorías al azar, como script.sh
+    Generate {
+        #[arg(long, default_value_t = 10)]
+        cantidad: u32,
+        #[arg(long, default_value_t = 30)]
+        duracion: u32,
+        #[arg(long, default_value_t = 1)]
+        intensidad: u32,
+        /// Categorías de donde se elige, se puede repetir. Sin ninguna es cualquiera.
+        #[arg(long = "categoria", value_parser = clap::builder::PossibleValuesParser::new(stress::CATEGORIAS))]
+        categorias: Vec<String>,
+    },
+    /// Consulta la configuración
+    Config {
+        #[command(subcommand)]
+        accion: AccionConfig,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum AccionConfig {
+    /// Imprime la configuración efectiva después de juntar archivo, entorno y opciones
+    Print,
+}
+
+#[derive(Debug, Default, Args)]
+struct OpcionesGrabacion {
+    /// Guarda cada lectura de /proc y de Docker en un archivo nuevo dentro de DIR
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = "grabaciones", conflicts_with = "reproducir")]
+    grabar: Option<PathBuf>,
+    /// Lee de una grabación en vez del módulo de kernel y Docker
+    #[arg(long, value_name = "ARCHIVO")]
+    reproducir: Option<PathBuf>,
+    /// Con --reproducir, 1 es tiempo real, 10 diez veces más rápido y 0 sin esperas
+    #[arg(long, default_value_t = 1.0, requires = "reproducir")]
+    velocidad: f64,
+}
+
+impl OpcionesGrabacion {
+    fn instalar(self) -> Result<(), Box<dyn Error>> {
+        let modo = match (self.grabar, self.reproducir) {
+            (Some(dir), _) => Modo::Grabar { dir },
+            (None, Some(archivo)) => Modo::Reproducir { archivo, velocidad: self.velocidad },
+            (None, None) => Modo::Directo,
+        };
+        if let Some(archivo) = grabacion::instalar(modo, &config::actual().rutas.sysinfo)? {
+            println!("Grabando en {}", archivo.display());
+        }
+        Ok(())
     }
 }
 
-async fn gestionar_contenedores_por_categoria(docker: &Docker, logger_container_id: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
-    // Leer información del sistema
-
-    if let Ok(sys_info) = leer_sysinfo() {
-        log::info!("=== Información del Sistema ===");
-        log::info!("RAM Total: {} KB", sys_info.system.ram_total);
-        log::info!("RAM Libre: {} KB", sys_info.system.ram_libre);
-        log::info!("RAM Ocupada: {} KB", sys_info.system.ram_ocupada);
-        log::info!("CPU Usada: {}%", sys_info.system.cpu_usada);
-        let cpu_json = sys_info.system.cpu_usada;
-        let ram_json = sys_info.system.ram_ocupada;
-        guardar_cpu_info(cpu_json);
-        guardar_ram_info(ram_json);
+#[derive(Debug, Subcommand)]
+enum AccionCron {
+    /// Instala o repara el bloque
+    Install,
+    /// Quita solo el bloque, el resto del crontab queda igual
+    Uninstall,
+    /// Dice si el bloque está instalado y al día
+    Status,
+}
 
-        
-        // Imprimir en consola de manera estilizada
-        println!("\n╔═════════════════════════════════════════╗");
-        println!("║           INFORMACIÓN DEL SISTEMA        ║");
-        println!("╠═════════════════════════════════════════╣");
-        println!("║ RAM Total:   {:10} KB              ║", sys_info.system.ram_total);
-        println!("║ RAM Libre:   {:10} KB              ║", sys_info.system.ram_libre);
-        println!("║ RAM Ocupada: {:10} KB              ║", sys_info.system.ram_ocupada);
-        println!("║ CPU Usada:   {:10}%                ║", sys_info.system.cpu_usada);
-        println!("╚═════════════════════════════════════════╝\n");
-    }
+#[actix_web::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
 
-    let filter = ListContainersOptions::<String> {
-        all: true,
-        ..Default::default()
-    };
-    
-    let containers = docker.list_containers(Some(filter)).await?;
-    
-    // Group containers by category
-    let mut container_categories: HashMap<String, Vec<(String, String, i64)>> = HashMap::new();
-    
-    for container in containers {
-        if let (Some(names), Some(id), Some(created)) = (&container.names, &container.id, container.created) {
-            for name in names {
-                if let Some(category) = clasificar_contenedor(name) {
-                    // Store id, name and creation time
-                    container_categories.entry(category).or_default().push((id.clone(), name.clone(), created));
-                }
-            }
-        }
-    }
-    
-    // Keep only the newest container of each type
-    let mut removed_containers = Vec::new();
-    
-    for (category, mut containers) in container_categories.iter_mut() {
-        // Sort by creation time (newest first)
-        containers.sort_by(|a, b| b.2.cmp(&a.2));
-        
-        // Keep the first one (newest), remove the rest
-        while containers.len() > 1 {
-            let (id, name, created) = containers.pop().unwrap();
-            
-            // Skip the logger container
-            if id == logger_container_id {
-                continue;
-            }
-            
-            log::info!("Eliminando contenedor de categoria {}: {} ({})", category, name, id);
-            
-            // Log container deletion with timestamp
-            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-            
-            let container_log = ContainerLog {
-                timestamp,
-                category: category.clone(),
-                name: name.clone(),
-                action: "eliminado".to_string(),
-            };
-            
-            removed_containers.push(container_log);
-            
-            let _ = docker.remove_container(&id, Some(RemoveContainerOptions { 
-                force: true, 
-                ..Default::default() 
-            })).await;
-        }
-    }
-    
-    // Print grouped containers
-    println!("╔═════════════════════════════════════════╗");
-    println!("║     CONTENEDORES ACTIVOS POR CATEGORÍA  ║");
-    println!("╠═════════════════════════════════════════╣");
-    
-    for (category, containers) in &container_categories {
-        println!("║ Categoría: {:<30} ║", category);
-        for (id, name, created) in containers {
-            // Here's where you need the safe conversion
-            let created_time = if *created >= 0 {
-                SystemTime::UNIX_EPOCH + Duration::from_secs(*created as u64)
-            } else {
-                SystemTime::UNIX_EPOCH // Use epoch time as default for negative values
-            };
-            
-            let created_str = format!("{:?}", created_time);
-            println!("║  - {}: {} (Creado: {}) ║", &id[0..12], name, created_str);
-        }
-        println!("╠═════════════════════════════════════════╣");
-    }
-    
-    println!("╚═════════════════════════════════════════╝\n");
-    
-    // Log removed containers
-    if !removed_containers.is_empty() {
-        println!("╔═════════════════════════════════════════╗");
-        println!("║          CONTENEDORES ELIMINADOS        ║");
-        println!("╠═════════════════════════════════════════╣");
-        
-        for log in &removed_containers {
-            println!("║ {} - {} - {} ║", log.category, log.name, log.timestamp);
+    let cli = Cli::parse();
+    // Con la configuración inválida no arranca ningún subcomando
+    let (configuracion, origenes) = match cli.cargar_config() {
+        Ok(cargada) => cargada,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
         }
-        
-        println!("╚═════════════════════════════════════════╝\n");
-        
-        // Send logs to the logger container
-        let logs_json = serde_json::to_string(&removed_containers)?;
-        Command::new("docker")
-            .args(&["exec", "http_request_logger", "sh", "-c", &format!("echo '{}' >> /logs/container_logs.json", logs_json)])
-            .output()?;
+    };
+    if let Some(Comando::Config { accion: AccionConfig::Print }) = &cli.comando {
+        print!("{}", config::imprimir(&configuracion, &origenes)?);
+        return Ok(());
     }
-    
-    Ok(())
-}
+    config::instalar(configuracion);
 
-async fn limpiar_contenedores(docker: &Docker) {
-    let filter = ListContainersOptions::<String> {
-        all: true,
-        ..Default::default()
-    };
-    let containers = docker.list_containers(Some(filter)).await.unwrap_or(vec![]);
-    let mut container_map: HashMap<String, Vec<String>> = HashMap::new();
-    
-    for container in containers {
-        if let Some(names) = &container.names {
-            for name in names {
-                if let Some(label) = clasificar_contenedor(name) {
-                    if let Some(id) = &container.id {
-                        container_map.entry(label).or_insert_with(Vec::new).push(id.clone());
-                    }
-                }
-            }
+    match cli.comando.unwrap_or(Comando::Serve { grabacion: OpcionesGrabacion::default() }) {
+        Comando::Serve { grabacion } => {
+            grabacion.instalar()?;
+            servidor::servir().await
         }
-    }
-    
-    for (_, ids) in container_map.iter_mut() {
-        ids.sort();
-        while ids.len() > 1 {
-            let id = ids.remove(0);
-            let _ = docker.remove_container(&id, Some(RemoveContainerOptions { force: true, ..Default::default() })).await;
+        Comando::Monitor { una_vez, grabacion } => {
+            grabacion.instalar()?;
+            servidor::monitorear(una_vez).await
         }
+        Comando::Cleanup { todos } => cleanup(todos).await,
+        Comando::Cron { accion } => cron(accion),
+        Comando::Dump { format, campos, watch, umbral_cpu, umbral_rss } => match watch {
+            Some(intervalo) => volcado::vigilar(format, intervalo, Umbrales { cpu: umbral_cpu, rss: umbral_rss }).await,
+            None => volcado::imprimir(format, &volcado::campos(&campos)?),
+        },
+        Comando::Top { intervalo } => tui::ejecutar(intervalo).await,
+        Comando::Generate { cantidad, duracion, intensidad, categorias } => generate(cantidad, duracion, intensidad, categorias).await,
+        Comando::Config { .. } => Ok(()),
     }
 }
 
-fn clasificar_contenedor(name: &str) -> Option<String> {
-    if name.contains("stress_ram") {
-        Some("ram".to_string())
-    } else if name.contains("stress_cpu") {
-        Some("cpu".to_string())
-    } else if name.contains("stress_io") {
-        Some("io".to_string())
-    } else if name.contains("stress_disk") {
-        Some("disk".to_string())
-    } else {
-        None
-    }
-}
-
-fn leer_sysinfo() -> Result<SystemInfo, Box<dyn Error>> {
-    let file_path = "/proc/sysinfo_202201724";  // Ajusta el archivo según sea necesario
-    let data = fs::read_to_string(file_path)?;
-    let sys_info: SystemInfo = serde_json::from_str(&data)?;
-    Ok(sys_info)
-}
-
-fn eliminar_cronjob() {
-    // Eliminar el cronjob de crontab
-    let output = Command::new("crontab")
-        .args(&["-r"])
-        .output()
-        .expect("No se pudo eliminar el cronjob");
-
-    // Comprobar si hubo algún error
-    if !output.status.success() {
-        eprintln!("Error al eliminar el cronjob: {}", String::from_utf8_lossy(&output.stderr));
-    } else {
-        println!("Cronjob eliminado correctamente");
+async fn cleanup(todos: bool) -> Result<(), Box<dyn Error>> {
+    let docker = Docker::connect_with_local_defaults()?;
+    let eliminados = limpiar_contenedores(&docker, todos, |_| false).await?;
+    for log in &eliminados {
+        println!("🗑 Eliminado contenedor de tipo {}: {}", log.category, log.name);
     }
+    println!("{} contenedores eliminados", eliminados.len());
+    Ok(())
 }
 
-fn imprimir_estado_final() {
-    if let Ok(sys_info) = leer_sysinfo() {
-        println!("╔═════════════════════════════════════════╗");
-        println!("║       INFORMACIÓN FINAL DEL SISTEMA     ║");
-        println!("╠═════════════════════════════════════════╣");
-        println!("║ RAM Total:   {:10} KB              ║", sys_info.system.ram_total);
-        println!("║ RAM Libre:   {:10} KB              ║", sys_info.system.ram_libre);
-        println!("║ RAM Ocupada: {:10} KB              ║", sys_info.system.ram_ocupada);
-        println!("║ CPU Usada:   {:10}%                ║", sys_info.system.cpu_usada);
-        println!("╚═════════════════════════════════════════╝\n");
+fn cron(accion: AccionCron) -> Result<(), Box<dyn Error>> {
+    let gestor = GestorCrontab::para_script(&config::actual().rutas.cron_script);
+    match accion {
+        AccionCron::Install => match gestor.instalar().map_err(|e| e.to_string())? {
+            Cambio::SinCambios => println!("El cronjob ya estaba instalado"),
+            Cambio::Reparado => println!("Cronjob reparado, el bloque en crontab no coincidía"),
+            _ => println!("Cronjob instalado"),
+        },
+        AccionCron::Uninstall => match gestor.desinstalar().map_err(|e| e.to_string())? {
+            Cambio::SinCambios => println!("No había cronjob que eliminar"),
+            _ => println!("Cronjob eliminado"),
+        },
+        AccionCron::Status => match gestor.revisar().map_err(|e| e.to_string())? {
+            EstadoBloque::Ausente => println!("No instalado"),
+            EstadoBloque::AlDia => println!("Instalado y al día"),
+            EstadoBloque::Desviado => println!("Instalado pero no coincide, use `cron install` para repararlo"),
+        },
     }
+    Ok(())
 }
 
-async fn get_logs() -> impl Responder {
-    let log_file_path = "/tmp/http_logs/requests.log"; // Ruta de los logs
-    let log_data = fs::read_to_string(log_file_path)
-        .unwrap_or_else(|_| "No se pudieron obtener los logs.".to_string());
-
-    HttpResponse::Ok().body(log_data)
-}
-
-async fn generate_graphs() -> impl Responder {
-    // Aquí se implementaría la generación de gráficas
-    // Por ahora, solo retornamos un mensaje de éxito
-    HttpResponse::Ok().body("Gráficas generadas correctamente")
-}
-
-fn configurar_cronjob() {
-    // Componentes del cronjob
-    let bash_interpreter = "/bin/bash";
-    let script_path = "/home/roberto/Documentos/sopes1/SOPES1_1S2025/PROYECTO1/sscripts/script.sh";
-
-    // Construir el comando crontab con ambas líneas
-    let cron_command = format!(
-        "(crontab -l 2>/dev/null; echo \"* * * * * {} {}\"; echo \"* * * * * sleep 30 && {} {}\") | crontab -",
-        bash_interpreter, script_path, bash_interpreter, script_path
-    );
-
-    // Ejecutar el comando
-    let output = Command::new("bash")
-        .arg("-c")
-        .arg(&cron_command)
-        .output()
-        .expect("No se pudo configurar el cronjob");
-
-    // Verificar el resultado
-    if !output.status.success() {
-        eprintln!("Error al configurar el cronjob: {}", String::from_utf8_lossy(&output.stderr));
-    } else {
-        println!("Cronjob configurado correctamente");
+async fn generate(cantidad: u32, duracion: u32, intensidad: u32, categorias: Vec<String>) -> Result<(), Box<dyn Error>> {
+    if cantidad == 0 || cantidad > stress::MAX_CANTIDAD {
+        return Err(format!("--cantidad debe estar entre 1 y {}", stress::MAX_CANTIDAD).into());
     }
-}
-
-fn guardar_cpu_info(cpu_usada: u8) -> std::io::Result<()> {
-    let file_path = "/home/roberto/Documentos/sopes1/SOPES1_1S2025/PROYECTO1/rust_services/graficas/cpu.json";
-    let path = Path::new(file_path);
-
-    // Crear directorio si no existe
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+    if intensidad == 0 || intensidad > stress::MAX_INTENSIDAD {
+        return Err(format!("--intensidad debe estar entre 1 y {}", stress::MAX_INTENSIDAD).into());
     }
-
-    let mut data = vec![];
-
-    // Leer archivo si existe
-    if path.exists() {
-        let mut file = fs::File::open(file_path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        
-        if !contents.trim().is_empty() {
-            data = serde_json::from_str(&contents).unwrap_or_else(|_| Vec::new());
-        }
+    if duracion == 0 || duracion > stress::MAX_DURACION {
+        return Err(format!("--duracion debe estar entre 1 y {}", stress::MAX_DURACION).into());
     }
 
-    // Agregar nuevo registro
-    let nuevo_registro = json!({
-        "timestamp": chrono::Utc::now().to_rfc3339(),
-        "cpu_usada": cpu_usada
-    });
-    data.push(nuevo_registro);
-
-    // Escribir datos actualizados en el archivo
-    let mut file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true) // Se debe sobrescribir el archivo
-        .open(file_path)?;
-    
-    let json_data = serde_json::to_string_pretty(&data)?;
-    file.write_all(json_data.as_bytes())?;
-    
-    Ok(())
-}
-
-fn guardar_ram_info(ram_ocupada: u64) -> std::io::Result<()> {
-    let file_path = "/home/roberto/Documentos/sopes1/SOPES1_1S2025/PROYECTO1/rust_services/graficas/ram.json";
-    let path = Path::new(file_path);
-
-    // Crear directorio si no existe
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+    let docker = Docker::connect_with_local_defaults()?;
+    let (logs, error) = stress::generar_carga(&docker, cantidad, duracion, intensidad, &categorias).await;
+    for log in &logs {
+        println!("Contenedor {} creado ({})", log.name, log.category);
     }
-
-    let mut data = vec![];
-
-    // Leer archivo si existe
-    if path.exists() {
-        let mut file = fs::File::open(file_path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        
-        if !contents.trim().is_empty() {
-            data = serde_json::from_str(&contents).unwrap_or_else(|_| Vec::new());
-        }
+    println!("Se han creado {} contenedores.", logs.len());
+    match error {
+        Some(e) => Err(e.into()),
+        None => Ok(()),
     }
-
-    // Agregar nuevo registro
-    let nuevo_registro = json!({
-        "timestamp": chrono::Utc::now().to_rfc3339(),
-        "ram_ocupada": ram_ocupada
-    });
-    data.push(nuevo_registro);
-
-    // Escribir datos actualizados en el archivo
-    let mut file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true) // Se debe sobrescribir el archivo
-        .open(file_path)?;
-    
-    let json_data = serde_json::to_string_pretty(&data)?;
-    file.write_all(json_data.as_bytes())?;
-    
-    Ok(())
 }
-
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    env_logger::init();
-    log::info!("Iniciando servicio...");
-
-    // Configurar el cronjob
-    configurar_cronjob();
-
-    // Configura y ejecuta el contenedor de logs
-    let container_id = start_logger_container();
-    let shutdown_flag = Arc::new(AtomicBool::new(false));
-    let docker = Arc::new(Docker::connect_with_local_defaults().unwrap());
-
-    let app_state = web::Data::new(AppState {
-        logger_container_id: container_id.clone(),
-        shutdown_flag: shutdown_flag.clone(),
-        docker: docker.clone(),
-    });
-
-    // Iniciar el monitoreo de contenedores
-    let docker_clone = docker.clone();
-    let container_id_clone = container_id.clone();
-    let _monitoring_task = tokio::spawn(async move {
-        gestionar_contenedores(&docker_clone, &container_id_clone).await;
-    });
-
-    // Maneja la señal de cierre
-    let container_id_clone = container_id.clone();
-    ctrlc::set_handler(move || {
-        log::info!("Señal de cierre recibida, finalizando...");
-        
-        // Imprimir información final
-        imprimir_estado_final();
-        
-        // Enviar petición final al contenedor de logs para generar gráficas
-        let output = Command::new("curl")
-            .args(&["-X", "POST", "http://localhost:5000/logs/generate_graphs"])
-            .output()
-            .expect("No se pudo enviar la petición final para generar gráficas");
-        
-        println!("Respuesta del contenedor de logs: {}", String::from_utf8_lossy(&output.stdout));
-        
-        // Eliminar el cronjob
-        eliminar_cronjob();
-        
-        std::process::exit(0);
-    }).expect("Error configurando el manejador de cierre");
-
-    // Iniciar el servidor HTTP
-    HttpServer::new(move || {
-        App::new()
-            .app_data(app_state.clone())
-            .wrap(middleware::Logger::default())
-            .service(web::resource("/").to(index))
-            .service(web::resource("/logs").to(get_logs)) // Ruta para obtener logs
-    })
-    .bind("0.0.0.0:5000")?
-    .run()
-    .await
-}
\ No newline at end of file
@@ -0,0 +1,419 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use crate::dashboard::ContenedorVivo;
+use crate::escenarios::FaseActiva;
+use crate::config;
+
+type Resultado<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+pub type Punto = (DateTime<Utc>, f64);
+
+// Máximo de puntos que devuelve /api/metrics en una consulta
+const MAX_PUNTOS: i64 = 10_000;
+const STEP_DEFECTO: i64 = 60;
+// Un step de más de un año no tiene sentido y así las cuentas con él no se desbordan
+const MAX_STEP: i64 = 366 * 24 * 3600;
+
+const CAMPOS_CONTENEDOR: [&str; 7] = [
+    "cpu_percent",
+    "memory_percent",
+    "memory_rss",
+    "virtual_memory",
+    "disk_usage",
+    "io_read_bytes",
+    "io_write_bytes",
+];
+
+#[derive(Debug, Deserialize)]
+struct RegistroCpu {
+    timestamp: String,
+    cpu_usada: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistroRam {
+    timestamp: String,
+    ram_ocupada: u64,
+}
+
+/// Una línea de `metricas_contenedores.ndjson`
+#[derive(Debug, Serialize, Deserialize)]
+struct RegistroContenedor {
+    timestamp: String,
+    pid: u32,
+    name: String,
+    categoria: Option<String>,
+    cpu_percent: u8,
+    memory_percent: u8,
+    memory_rss: u64,
+    virtual_memory: u64,
+    disk_usage: u64,
+    io_read_bytes: u64,
+    io_write_bytes: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    escenario: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fase: Option<String>,
+}
+
+impl RegistroContenedor {
+    fn campo(&self, campo: &str) -> Option<f64> {
+        match campo {
+            "cpu_percent" => Some(self.cpu_percent as f64),
+            "memory_percent" => Some(self.memory_percent as f64),
+            "memory_rss" => Some(self.memory_rss as f64),
+            "virtual_memory" => Some(self.virtual_memory as f64),
+            "disk_usage" => Some(self.disk_usage as f64),
+            "io_read_bytes" => Some(self.io_read_bytes as f64),
+            "io_write_bytes" => Some(self.io_write_bytes as f64),
+            _ => None,
+        }
+    }
+}
+
+pub fn leer_registros<T: for<'de> Deserialize<'de>>(file_path: &str) -> Resultado<Vec<T>> {
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(path)?;
+    if contents.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    Ok(serde_json::from_str(&contents)?)
+}
+
+pub fn parsear_fecha(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp).ok().map(|t| t.with_timezone(&Utc))
+}
+
+/// Serie de `cpu.json` en porcentaje
+pub fn leer_cpu(file_path: &str) -> Resultado<Vec<Punto>> {
+    let registros: Vec<RegistroCpu> = leer_registros(file_path)?;
+    Ok(registros
+        .iter()
+        .filter_map(|r| parsear_fecha(&r.timestamp).map(|t| (t, r.cpu_usada as f64)))
+        .collect())
+}
+
+/// Serie de `ram.json` en KB
+pub fn leer_ram(file_path: &str) -> Resultado<Vec<Punto>> {
+    let registros: Vec<RegistroRam> = leer_registros(file_path)?;
+    Ok(registros
+        .iter()
+        .filter_map(|r| parsear_fecha(&r.timestamp).map(|t| (t, r.ram_ocupada as f64)))
+        .collect())
+}
+
+/// Serie de un campo de un proceso (por PID) de `metricas_contenedores.ndjson`
+pub fn leer_contenedor(file_path: &str, pid: u32, campo: &str) -> Resultado<Vec<Punto>> {
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut puntos = Vec::new();
+    for linea in BufReader::new(fs::File::open(path)?).lines() {
+        let linea = linea?;
+        // Una línea cortada (por ejemplo si se apagó a medio escribir) se ignora
+        let registro: RegistroContenedor = match serde_json::from_str(&linea) {
+            Ok(registro) => registro,
+            Err(_) => continue,
+        };
+        if registro.pid != pid {
+            continue;
+        }
+        if let (Some(t), Some(valor)) = (parsear_fecha(&registro.timestamp), registro.campo(campo)) {
+            puntos.push((t, valor));
+        }
+    }
+    Ok(puntos)
+}
+
+/// Agrega una línea por proceso al archivo de métricas de contenedores, con la fase del escenario si hay uno
+pub fn guardar_contenedores(file_path: &str, containers: &[ContenedorVivo], fase: Option<&FaseActiva>) -> std::io::Result<()> {
+    if containers.is_empty() {
+        return Ok(());
+    }
+
+    let path = Path::new(file_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let timestamp = Utc::now().to_rfc3339();
+    let mut lineas = String::new();
+    for c in containers {
+        let registro = RegistroContenedor {
+            timestamp: timestamp.clone(),
+            pid: c.info.pid,
+            name: c.info.name.clone(),
+            categoria: c.categoria.clone(),
+            cpu_percent: c.info.cpu_percent,
+            memory_percent: c.info.memory_percent,
+            memory_rss: c.info.memory_rss,
+            virtual_memory: c.info.virtual_memory,
+            disk_usage: c.info.disk_usage,
+            io_read_bytes: c.info.io_read_bytes,
+            io_write_bytes: c.info.io_write_bytes,
+            escenario: fase.map(|f| f.escenario.clone()),
+            fase: fase.map(|f| f.fase.clone()),
+        };
+        lineas.push_str(&serde_json::to_string(&registro)?);
+        lineas.push('\n');
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lineas.as_bytes())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Agregacion {
+    Promedio,
+    Minimo,
+    Maximo,
+}
+
+impl Agregacion {
+    fn parsear(valor: &str) -> Option<Self> {
+        match valor {
+            "avg" => Some(Agregacion::Promedio),
+            "min" => Some(Agregacion::Minimo),
+            "max" => Some(Agregacion::Maximo),
+            _ => None,
+        }
+    }
+
+    fn aplicar(self, valores: &[f64]) -> Option<f64> {
+        if valores.is_empty() {
+            return None;
+        }
+        Some(match self {
+            Agregacion::Promedio => valores.iter().sum::<f64>() / valores.len() as f64,
+            Agregacion::Minimo => valores.iter().cloned().fold(f64::INFINITY, f64::min),
+            Agregacion::Maximo => valores.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+        })
+    }
+}
+
+/// Agrupa los puntos en intervalos de `step` segundos alineados a múltiplos de `step`.
+/// Los intervalos sin muestras quedan en None para que todas las series tengan la misma forma.
+pub fn reducir(puntos: &[Punto], desde: i64, hasta: i64, step: i64, agregacion: Agregacion) -> Vec<(i64, Option<f64>)> {
+    let inicio = desde.div_euclid(step) * step;
+    let cantidad = ((hasta - inicio) / step + 1).max(0) as usize;
+    let mut grupos: Vec<Vec<f64>> = vec![Vec::new(); cantidad];
+
+    for (t, valor) in puntos {
+        let segundos = t.timestamp();
+        if segundos < desde || segundos > hasta {
+            continue;
+        }
+        let indice = ((segundos - inicio) / step) as usize;
+        if let Some(grupo) = grupos.get_mut(indice) {
+            grupo.push(*valor);
+        }
+    }
+
+    grupos
+        .iter()
+        .enumerate()
+        .map(|(i, valores)| (inicio + i as i64 * step, agregacion.aplicar(valores)))
+        .collect()
+}
+
+/// Acepta RFC3339 o segundos desde epoch, solo dentro del rango que puede representar chrono
+pub fn parsear_instante(valor: &str) -> Option<i64> {
+    valor
+        .parse::<i64>()
+        .ok()
+        .filter(|t| DateTime::from_timestamp(*t, 0).is_some())
+        .or_else(|| parsear_fecha(valor).map(|t| t.timestamp()))
+}
+
+fn rfc3339(segundos: i64) -> String {
+    DateTime::from_timestamp(segundos, 0).map(|t| t.to_rfc3339()).unwrap_or_default()
+}
+
+/// Acepta segundos (`60`) o con unidad (`30s`, `5m`, `1h`)
+fn parsear_step(valor: &str) -> Option<i64> {
+    let (numero, multiplicador) = match valor.chars().last()? {
+        's' => (&valor[..valor.len() - 1], 1),
+        'm' => (&valor[..valor.len() - 1], 60),
+        'h' => (&valor[..valor.len() - 1], 3600),
+        _ => (valor, 1),
+    };
+    numero.parse::<i64>().ok().filter(|n| *n > 0).and_then(|n| n.checked_mul(multiplicador))
+        .filter(|step| *step <= MAX_STEP)
+}
+
+/// Query de `GET /api/metrics`
+/// Ej: `/api/metrics?series=container:1234:memory_rss&from=2025-03-14T23:00:00Z&step=5m&agg=max`
+#[derive(Debug, Deserialize)]
+pub struct ConsultaMetricas {
+    series: String,
+    from: Option<String>,
+    to: Option<String>,
+    step: Option<String>,
+    agg: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PuntoRespuesta {
+    timestamp: String,
+    valor: Option<f64>,
+}
+
+fn error_json(status: StatusCode, mensaje: String) -> HttpResponse {
+    HttpResponse::build(status).json(json!({ "error": mensaje }))
+}
+
+/// `GET /api/metrics`, series: `cpu`, `ram` o `container:<pid>[:<campo>]`
+pub async fn get_metrics(consulta: web::Query<ConsultaMetricas>) -> impl Responder {
+    let consulta = consulta.into_inner();
+
+    let agregacion = match Agregacion::parsear(consulta.agg.as_deref().unwrap_or("avg")) {
+        Some(agregacion) => agregacion,
+        None => return error_json(StatusCode::BAD_REQUEST, "agg debe ser avg, min o max".to_string()),
+    };
+    let step = match consulta.step.as_deref().map(parsear_step).unwrap_or(Some(STEP_DEFECTO)) {
+        Some(step) => step,
+        None => return error_json(StatusCode::BAD_REQUEST, "step no válido, use segundos o 30s, 5m, 1h, como mucho un año".to_string()),
+    };
+    let desde = match consulta.from.as_deref().map(parsear_instante) {
+        Some(None) => return error_json(StatusCode::BAD_REQUEST, "from no es una fecha válida".to_string()),
+        Some(Some(desde)) => Some(desde),
+        None => None,
+    };
+    let hasta = match consulta.to.as_deref().map(parsear_instante) {
+        Some(None) => return error_json(StatusCode::BAD_REQUEST, "to no es una fecha válida".to_string()),
+        Some(Some(hasta)) => hasta,
+        None => Utc::now().timestamp(),
+    };
+
+    let series = consulta.series.clone();
+    let lectura = web::block(move || -> Resultado<Option<Vec<Punto>>> {
+        let partes: Vec<&str> = series.split(':').collect();
+        let rutas = &config::actual().rutas;
+        Ok(match partes.as_slice() {
+            ["cpu"] => Some(leer_cpu(&rutas.cpu_json())?),
+            ["ram"] => Some(leer_ram(&rutas.ram_json())?),
+            ["container", pid] | ["container", pid, _] => {
+                let campo = partes.get(2).copied().unwrap_or("cpu_percent");
+                match pid.parse::<u32>() {
+                    Ok(pid) if CAMPOS_CONTENEDOR.contains(&campo) => {
+                        Some(leer_contenedor(&rutas.metricas_contenedores(), pid, campo)?)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+    })
+    .await;
+
+    let puntos = match lectura {
+        Ok(Ok(Some(puntos))) => puntos,
+        Ok(Ok(None)) => {
+            return error_json(StatusCode::BAD_REQUEST, format!("Serie '{}' no válida, use cpu, ram o container:<pid>[:<campo>]", consulta.series))
+        }
+        Ok(Err(e)) => return error_json(StatusCode::INTERNAL_SERVER_ERROR, format!("No se pudieron leer las métricas: {}", e)),
+        Err(e) => return error_json(StatusCode::INTERNAL_SERVER_ERROR, format!("No se pudieron leer las métricas: {}", e)),
+    };
+
+    // Sin from se empieza en la primera muestra guardada
+    let desde = desde.unwrap_or_else(|| puntos.iter().map(|p| p.0.timestamp()).min().unwrap_or(hasta));
+    if desde > hasta {
+        return error_json(StatusCode::BAD_REQUEST, "from debe ser anterior a to".to_string());
+    }
+    if hasta.checked_sub(desde).is_none_or(|rango| rango / step > MAX_PUNTOS) {
+        return error_json(StatusCode::BAD_REQUEST, format!("La consulta pide más de {} puntos, use un step mayor", MAX_PUNTOS));
+    }
+
+    let puntos: Vec<PuntoRespuesta> = reducir(&puntos, desde, hasta, step, agregacion)
+        .into_iter()
+        .map(|(t, valor)| PuntoRespuesta {
+            timestamp: rfc3339(t),
+            valor,
+        })
+        .collect();
+
+    HttpResponse::Ok().json(json!({
+        "series": consulta.series,
+        "agg": consulta.agg.unwrap_or_else(|| "avg".to_string()),
+        "step": step,
+        "from": rfc3339(desde),
+        "to": rfc3339(hasta),
+        "puntos": puntos,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn punto(segundos: i64, valor: f64) -> Punto {
+        (DateTime::from_timestamp(segundos, 0).unwrap(), valor)
+    }
+
+    #[test]
+    fn parsear_step_con_y_sin_unidad() {
+        assert_eq!(parsear_step("60"), Some(60));
+        assert_eq!(parsear_step("30s"), Some(30));
+        assert_eq!(parsear_step("5m"), Some(300));
+        assert_eq!(parsear_step("1h"), Some(3600));
+        assert_eq!(parsear_step("0"), None);
+        assert_eq!(parsear_step("-5m"), None);
+        assert_eq!(parsear_step("m"), None);
+        assert_eq!(parsear_step("5d"), None);
+        assert_eq!(parsear_step(""), None);
+    }
+
+    #[test]
+    fn parsear_step_no_se_desborda() {
+        assert_eq!(parsear_step("9223372036854775807h"), None);
+        assert_eq!(parsear_step("9223372036854775807"), None);
+        assert_eq!(parsear_step("8784h"), Some(MAX_STEP));
+        assert_eq!(parsear_step("8785h"), None);
+    }
+
+    #[test]
+    fn parsear_instante_fuera_de_rango() {
+        assert_eq!(parsear_instante("1741993200"), Some(1741993200));
+        assert_eq!(parsear_instante("2025-03-14T23:00:00Z"), Some(1741993200));
+        assert_eq!(parsear_instante("99999999999999"), None);
+        assert_eq!(parsear_instante("-9223372036854775808"), None);
+        assert_eq!(parsear_instante("ayer"), None);
+    }
+
+    #[test]
+    fn reducir_alinea_a_step_y_deja_huecos() {
+        let puntos = [punto(60, 1.0), punto(90, 3.0), punto(200, 10.0), punto(500, 99.0)];
+        let grupos = reducir(&puntos, 70, 250, 60, Agregacion::Promedio);
+        // El primer intervalo empieza en 60 aunque desde sea 70, el punto de 60 queda fuera
+        assert_eq!(grupos, vec![(60, Some(3.0)), (120, None), (180, Some(10.0)), (240, None)]);
+    }
+
+    #[test]
+    fn reducir_con_cada_agregacion() {
+        let puntos = [punto(0, 4.0), punto(10, 1.0), punto(20, 7.0)];
+        assert_eq!(reducir(&puntos, 0, 59, 60, Agregacion::Promedio), vec![(0, Some(4.0))]);
+        assert_eq!(reducir(&puntos, 0, 59, 60, Agregacion::Minimo), vec![(0, Some(1.0))]);
+        assert_eq!(reducir(&puntos, 0, 59, 60, Agregacion::Maximo), vec![(0, Some(7.0))]);
+    }
+
+    #[test]
+    fn reducir_con_instantes_negativos() {
+        let puntos = [punto(-90, 2.0), punto(-10, 6.0)];
+        assert_eq!(reducir(&puntos, -100, 0, 60, Agregacion::Maximo), vec![(-120, Some(2.0)), (-60, Some(6.0)), (0, None)]);
+    }
+}
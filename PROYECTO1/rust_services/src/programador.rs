@@ -0,0 +1,606 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, Responder};
+use chrono::{DateTime, Local, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::{config, enviar_container_logs, grabacion, limpiar_contenedores, stress, AppState};
+
+type Resultado<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+// Errores de las operaciones del programador con el código HTTP que les toca
+type ResultadoApi<T> = Result<T, (StatusCode, String)>;
+
+const PROXIMAS_DEFECTO: usize = 20;
+const PROXIMAS_MAX: usize = 500;
+// Hasta un año hacia adelante en `?horas=`
+const PROXIMAS_HORAS_MAX: u32 = 366 * 24;
+
+/// Lo que hace cada trabajo programado
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "tipo", rename_all = "snake_case")]
+pub enum Trabajo {
+    /// Lo mismo que `script.sh`: crea contenedores de stress de categorías al azar
+    GenerarCarga {
+        #[serde(default = "cantidad_defecto")]
+        cantidad: u32,
+        #[serde(default = "duracion_defecto")]
+        duracion_segundos: u32,
+        #[serde(default = "intensidad_defecto")]
+        intensidad: u32,
+        /// Vacío es cualquiera de `stress::CATEGORIAS`
+        #[serde(default)]
+        categorias: Vec<String>,
+    },
+    /// Deja un solo contenedor de stress por categoría, incluidos los detenidos
+    Limpieza,
+    /// Guarda la última instantánea del monitor como JSON
+    ExportarInstantanea {
+        #[serde(default)]
+        dir: Option<String>,
+    },
+}
+
+fn cantidad_defecto() -> u32 {
+    10
+}
+
+fn duracion_defecto() -> u32 {
+    30
+}
+
+fn intensidad_defecto() -> u32 {
+    1
+}
+
+fn verdadero() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigTrabajo {
+    /// En `PUT /api/programador/{nombre}` se puede omitir, se toma de la ruta
+    #[serde(default)]
+    pub nombre: String,
+    /// Cron con segundos (`*/30 * * * * *`) o de 5 campos como el del sistema (`*/5 * * * *`)
+    pub expresion: String,
+    #[serde(flatten)]
+    pub trabajo: Trabajo,
+    #[serde(default = "verdadero")]
+    pub habilitado: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigProgramador {
+    /// Con `true` `script.sh` se sigue corriendo desde el crontab del sistema
+    pub crontab: bool,
+    /// Donde se guardan los trabajos que se crean o cambian por la API. Si existe,
+    /// manda sobre `trabajos`, que solo sirven para la primera vez.
+    pub archivo: String,
+    pub trabajos: Vec<ConfigTrabajo>,
+}
+
+impl Default for ConfigProgramador {
+    fn default() -> Self {
+        ConfigProgramador {
+            crontab: true,
+            archivo: "programador.json".to_string(),
+            trabajos: Vec::new(),
+        }
+    }
+}
+
+/// Lo que se sabe de un trabajo, se sirve en `GET /api/programador`
+#[derive(Debug, Clone, Serialize)]
+pub struct EstadoTrabajo {
+    #[serde(flatten)]
+    pub config: ConfigTrabajo,
+    pub ultima_ejecucion: Option<String>,
+    pub ultima_duracion_ms: Option<u64>,
+    pub ultimo_resultado: Option<String>,
+    pub ultimo_error: Option<String>,
+    pub proxima_ejecucion: Option<String>,
+    pub ejecuciones: u64,
+    pub fallos: u64,
+}
+
+/// Una ejecución futura, para `GET /api/programador/proximas`
+#[derive(Debug, Serialize)]
+pub struct Ejecucion {
+    pub nombre: String,
+    pub tipo: &'static str,
+    pub fecha: String,
+}
+
+/// Corre los trabajos dentro del servicio, cada uno en su propia tarea de tokio
+pub struct Programador {
+    archivo: PathBuf,
+    trabajos: Mutex<Vec<EstadoTrabajo>>,
+    tareas: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+/// Acepta expresiones de 5 campos agregando el campo de segundos en 0
+pub fn parsear_expresion(expresion: &str) -> Result<Schedule, String> {
+    let expresion = expresion.trim();
+    let completa = if expresion.split_whitespace().count() == 5 {
+        format!("0 {}", expresion)
+    } else {
+        expresion.to_string()
+    };
+    Schedule::from_str(&completa).map_err(|e| format!("Expresión cron '{}' no válida: {}", expresion, e))
+}
+
+/// Revisa un trabajo antes de registrarlo
+pub fn validar(config: &ConfigTrabajo) -> Result<(), String> {
+    if config.nombre.trim().is_empty() {
+        return Err("El trabajo necesita un nombre".to_string());
+    }
+    // El nombre va en la URL de la API
+    if !config.nombre.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("El nombre solo puede tener letras, números, '-' y '_'".to_string());
+    }
+    if config.nombre == "proximas" {
+        return Err("'proximas' está reservado".to_string());
+    }
+    parsear_expresion(&config.expresion)?;
+
+    if let Trabajo::GenerarCarga { cantidad, duracion_segundos, intensidad, categorias } = &config.trabajo {
+        if *cantidad == 0 || *cantidad > stress::MAX_CANTIDAD {
+            return Err(format!("cantidad debe estar entre 1 y {}", stress::MAX_CANTIDAD));
+        }
+        if *intensidad == 0 || *intensidad > stress::MAX_INTENSIDAD {
+            return Err(format!("intensidad debe estar entre 1 y {}", stress::MAX_INTENSIDAD));
+        }
+        if *duracion_segundos == 0 || *duracion_segundos > stress::MAX_DURACION {
+            return Err(format!("duracion_segundos debe estar entre 1 y {}", stress::MAX_DURACION));
+        }
+        if let Some(categoria) = categorias.iter().find(|c| !stress::CATEGORIAS.contains(&c.as_str())) {
+            return Err(format!("Categoría '{}' no válida, use una de {:?}", categoria, stress::CATEGORIAS));
+        }
+    }
+    Ok(())
+}
+
+impl Programador {
+    /// Toma los trabajos del archivo del programador si existe, si no los de `config.toml`
+    pub fn new(config: ConfigProgramador) -> Result<Self, String> {
+        let archivo = PathBuf::from(&config.archivo);
+        let trabajos = if archivo.exists() {
+            let data = fs::read_to_string(&archivo).map_err(|e| format!("No se pudo leer {}: {}", archivo.display(), e))?;
+            let trabajos: Vec<ConfigTrabajo> =
+                serde_json::from_str(&data).map_err(|e| format!("Error en {}: {}", archivo.display(), e))?;
+            log::info!("{} trabajos cargados de {}", trabajos.len(), archivo.display());
+            trabajos
+        } else {
+            config.trabajos
+        };
+
+        let mut estados: Vec<EstadoTrabajo> = Vec::new();
+        for config in trabajos {
+            validar(&config).map_err(|e| format!("Trabajo '{}': {}", config.nombre, e))?;
+            if estados.iter().any(|e| e.config.nombre == config.nombre) {
+                return Err(format!("Hay dos trabajos con el nombre '{}'", config.nombre));
+            }
+            estados.push(estado_inicial(config));
+        }
+        Ok(Programador {
+            archivo,
+            trabajos: Mutex::new(estados),
+            tareas: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Arranca una tarea por cada trabajo habilitado
+    pub fn iniciar(&self, app: web::Data<AppState>) {
+        let nombres: Vec<String> = self.trabajos.lock().unwrap().iter().map(|e| e.config.nombre.clone()).collect();
+        for nombre in nombres {
+            self.reprogramar(&app, &nombre);
+        }
+    }
+
+    /// Cancela todas las tareas, para el cierre del servicio
+    pub fn detener(&self) {
+        for (_, tarea) in self.tareas.lock().unwrap().drain() {
+            tarea.abort();
+        }
+    }
+
+    pub fn estados(&self) -> Vec<EstadoTrabajo> {
+        self.trabajos.lock().unwrap().clone()
+    }
+
+    pub fn estado(&self, nombre: &str) -> Option<EstadoTrabajo> {
+        self.trabajos.lock().unwrap().iter().find(|e| e.config.nombre == nombre).cloned()
+    }
+
+    pub fn crear(&self, config: ConfigTrabajo) -> ResultadoApi<EstadoTrabajo> {
+        validar(&config).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        let mut trabajos = self.trabajos.lock().unwrap();
+        if trabajos.iter().any(|e| e.config.nombre == config.nombre) {
+            return Err((StatusCode::CONFLICT, format!("Ya existe un trabajo '{}'", config.nombre)));
+        }
+
+        let mut nuevos = trabajos.clone();
+        nuevos.push(estado_inicial(config));
+        self.guardar(&nuevos)?;
+        *trabajos = nuevos;
+        Ok(trabajos.last().cloned().unwrap())
+    }
+
+    /// Cambia la configuración de un trabajo, las estadísticas de ejecuciones se conservan
+    pub fn reemplazar(&self, nombre: &str, config: ConfigTrabajo) -> ResultadoApi<EstadoTrabajo> {
+        validar(&config).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        let mut trabajos = self.trabajos.lock().unwrap();
+        let indice = trabajos
+            .iter()
+            .position(|e| e.config.nombre == nombre)
+            .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No existe el trabajo '{}'", nombre)))?;
+
+        let mut nuevos = trabajos.clone();
+        nuevos[indice].config = config;
+        nuevos[indice].proxima_ejecucion = None;
+        self.guardar(&nuevos)?;
+        *trabajos = nuevos;
+        Ok(trabajos[indice].clone())
+    }
+
+    pub fn eliminar(&self, nombre: &str) -> ResultadoApi<()> {
+        let mut trabajos = self.trabajos.lock().unwrap();
+        if !trabajos.iter().any(|e| e.config.nombre == nombre) {
+            return Err((StatusCode::NOT_FOUND, format!("No existe el trabajo '{}'", nombre)));
+        }
+
+        let nuevos: Vec<EstadoTrabajo> = trabajos.iter().filter(|e| e.config.nombre != nombre).cloned().collect();
+        self.guardar(&nuevos)?;
+        *trabajos = nuevos;
+        Ok(())
+    }
+
+    /// Detiene la tarea del trabajo y arranca otra con la configuración actual.
+    /// Si estaba a medio ejecutar se cancela; si ya no existe o está deshabilitado no se arranca otra.
+    pub fn reprogramar(&self, app: &web::Data<AppState>, nombre: &str) {
+        let mut tareas = self.tareas.lock().unwrap();
+        if let Some(tarea) = tareas.remove(nombre) {
+            tarea.abort();
+        }
+        match self.config(nombre) {
+            // Los trabajos que se crean o editan por la API tampoco corren al reproducir
+            Some(config) if config.habilitado && !grabacion::reproduciendo() => {
+                // Se calcula aquí también para que la respuesta de la API ya la traiga
+                let proxima = siguiente(&config.expresion);
+                self.actualizar(nombre, |e| e.proxima_ejecucion = proxima.map(|f| f.to_rfc3339()));
+                tareas.insert(nombre.to_string(), tokio::spawn(correr_trabajo(app.clone(), nombre.to_string())));
+            }
+            _ => self.actualizar(nombre, |e| e.proxima_ejecucion = None),
+        }
+    }
+
+    /// Las próximas `limite` ejecuciones de los trabajos habilitados, en orden
+    pub fn proximas(&self, limite: usize, hasta: Option<DateTime<Utc>>) -> Vec<Ejecucion> {
+        let trabajos = self.trabajos.lock().unwrap();
+        let mut fechas: Vec<(DateTime<Utc>, &ConfigTrabajo)> = Vec::new();
+        for config in trabajos.iter().map(|e| &e.config).filter(|c| c.habilitado) {
+            if let Ok(schedule) = parsear_expresion(&config.expresion) {
+                let siguientes = schedule.upcoming(Local).map(|f| f.with_timezone(&Utc)).take_while(|f| hasta.is_none_or(|h| *f <= h)).take(limite);
+                fechas.extend(siguientes.map(|f| (f, config)));
+            }
+        }
+        fechas.sort_by_key(|(f, _)| *f);
+        fechas
+            .into_iter()
+            .take(limite)
+            .map(|(f, config)| Ejecucion { nombre: config.nombre.clone(), tipo: config.trabajo.tipo(), fecha: f.to_rfc3339() })
+            .collect()
+    }
+
+    // Se escribe a un temporal y se renombra para no dejar el archivo a medias
+    fn guardar(&self, trabajos: &[EstadoTrabajo]) -> ResultadoApi<()> {
+        let configs: Vec<&ConfigTrabajo> = trabajos.iter().map(|e| &e.config).collect();
+        let temporal = self.archivo.with_extension("json.tmp");
+        let resultado = serde_json::to_string_pretty(&configs)
+            .map_err(|e| e.to_string())
+            .and_then(|data| fs::write(&temporal, data).map_err(|e| e.to_string()))
+            .and_then(|_| fs::rename(&temporal, &self.archivo).map_err(|e| e.to_string()));
+
+        resultado.map_err(|e| {
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("No se pudo guardar {}: {}", self.archivo.display(), e))
+        })
+    }
+
+    fn config(&self, nombre: &str) -> Option<ConfigTrabajo> {
+        let trabajos = self.trabajos.lock().unwrap();
+        trabajos.iter().find(|e| e.config.nombre == nombre).map(|e| e.config.clone())
+    }
+
+    fn actualizar(&self, nombre: &str, cambio: impl FnOnce(&mut EstadoTrabajo)) {
+        let mut trabajos = self.trabajos.lock().unwrap();
+        if let Some(estado) = trabajos.iter_mut().find(|e| e.config.nombre == nombre) {
+            cambio(estado);
+        }
+    }
+}
+
+impl Trabajo {
+    pub fn tipo(&self) -> &'static str {
+        match self {
+            Trabajo::GenerarCarga { .. } => "generar_carga",
+            Trabajo::Limpieza => "limpieza",
+            Trabajo::ExportarInstantanea { .. } => "exportar_instantanea",
+        }
+    }
+}
+
+fn estado_inicial(config: ConfigTrabajo) -> EstadoTrabajo {
+    EstadoTrabajo {
+        config,
+        ultima_ejecucion: None,
+        ultima_duracion_ms: None,
+        ultimo_resultado: None,
+        ultimo_error: None,
+        proxima_ejecucion: None,
+        ejecuciones: 0,
+        fallos: 0,
+    }
+}
+
+// Las expresiones se evalúan en la hora local, igual que el crontab del sistema
+fn siguiente(expresion: &str) -> Option<DateTime<Utc>> {
+    parsear_expresion(expresion).ok().and_then(|s| s.upcoming(Local).next()).map(|f| f.with_timezone(&Utc))
+}
+
+async fn correr_trabajo(app: web::Data<AppState>, nombre: String) {
+    loop {
+        // Se vuelve a leer en cada vuelta por si el trabajo cambió
+        let config = match app.programador.config(&nombre) {
+            Some(config) if config.habilitado => config,
+            _ => return,
+        };
+        let proxima = match siguiente(&config.expresion) {
+            Some(proxima) => proxima,
+            None => {
+                log::warn!("El trabajo '{}' no tiene más ejecuciones", nombre);
+                app.programador.actualizar(&nombre, |e| e.proxima_ejecucion = None);
+                return;
+            }
+        };
+        app.programador.actualizar(&nombre, |e| e.proxima_ejecucion = Some(proxima.to_rfc3339()));
+
+        let espera = (proxima - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(espera).await;
+
+        // Si una ejecución tarda más que el intervalo, las que se pasaron no se recuperan
+        let inicio = Utc::now();
+        let resultado = ejecutar(&app, &config.trabajo).await;
+        let duracion = (Utc::now() - inicio).num_milliseconds().max(0) as u64;
+
+        match &resultado {
+            Ok(mensaje) => log::info!("Trabajo '{}': {}", nombre, mensaje),
+            Err(e) => log::error!("Trabajo '{}' falló: {}", nombre, e),
+        }
+        app.programador.actualizar(&nombre, |e| {
+            e.ultima_ejecucion = Some(inicio.to_rfc3339());
+            e.ultima_duracion_ms = Some(duracion);
+            e.ejecuciones += 1;
+            match resultado {
+                Ok(mensaje) => {
+                    e.ultimo_resultado = Some(mensaje);
+                    e.ultimo_error = None;
+                }
+                Err(error) => {
+                    e.fallos += 1;
+                    e.ultimo_error = Some(error.to_string());
+                }
+            }
+        });
+    }
+}
+
+async fn ejecutar(app: &AppState, trabajo: &Trabajo) -> Resultado<String> {
+    match trabajo {
+        Trabajo::GenerarCarga { cantidad, duracion_segundos, intensidad, categorias } => {
+            generar_carga(app, *cantidad, *duracion_segundos, *intensidad, categorias).await
+        }
+        Trabajo::Limpieza => {
+            let eliminados = limpiar_contenedores(&app.docker, false, |id| app.protegido(id)).await?;
+            for log in &eliminados {
+                app.sesion.registrar_eliminado(&log.category);
+            }
+            app.monitor.registrar_eventos(&eliminados);
+            Ok(format!("{} contenedores de stress eliminados", eliminados.len()))
+        }
+        Trabajo::ExportarInstantanea { dir } => {
+            let dir = match dir {
+                Some(dir) => PathBuf::from(dir),
+                None => Path::new(&config::actual().rutas.graficas).join("instantaneas"),
+            };
+            let instantanea = app.monitor.ultima().ok_or("El monitor todavía no tiene una instantánea")?;
+            let archivo = web::block(move || -> Resultado<PathBuf> {
+                fs::create_dir_all(&dir)?;
+                let archivo = dir.join(format!("instantanea_{}.json", Utc::now().format("%Y%m%dT%H%M%SZ")));
+                fs::write(&archivo, serde_json::to_string_pretty(&instantanea)?)?;
+                Ok(archivo)
+            })
+            .await??;
+            Ok(format!("Instantánea guardada en {}", archivo.display()))
+        }
+    }
+}
+
+async fn generar_carga(app: &AppState, cantidad: u32, duracion: u32, intensidad: u32, categorias: &[String]) -> Resultado<String> {
+    let (logs, error) = stress::generar_carga(&app.docker, cantidad, duracion, intensidad, categorias).await;
+
+    let creados = logs.len();
+    if !logs.is_empty() {
+        app.monitor.registrar_eventos(&logs);
+        if let Ok(Err(e)) = web::block(move || enviar_container_logs(&logs)).await {
+            log::error!("No se pudo escribir el log del contenedor: {}", e);
+        }
+    }
+
+    match error {
+        None => Ok(format!("{} contenedores de stress creados", creados)),
+        Some(e) => Err(format!("Se crearon {} de {} contenedores: {}", creados, cantidad, e).into()),
+    }
+}
+
+fn error_json(status: StatusCode, mensaje: String) -> HttpResponse {
+    HttpResponse::build(status).json(json!({ "error": mensaje }))
+}
+
+/// `GET /api/programador`
+pub async fn get_trabajos(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(data.programador.estados())
+}
+
+/// `GET /api/programador/{nombre}`
+pub async fn get_trabajo(data: web::Data<AppState>, nombre: web::Path<String>) -> impl Responder {
+    match data.programador.estado(&nombre) {
+        Some(estado) => HttpResponse::Ok().json(estado),
+        None => error_json(StatusCode::NOT_FOUND, format!("No existe el trabajo '{}'", nombre)),
+    }
+}
+
+/// `POST /api/programador`, crea un trabajo y lo arranca si está habilitado
+pub async fn post_trabajo(data: web::Data<AppState>, config: web::Json<ConfigTrabajo>) -> impl Responder {
+    let config = config.into_inner();
+    let nombre = config.nombre.clone();
+    let programador = data.programador.clone();
+
+    match web::block(move || programador.crear(config)).await {
+        Ok(Ok(_)) => {
+            log::info!("Trabajo '{}' creado desde la API", nombre);
+            data.programador.reprogramar(&data, &nombre);
+            HttpResponse::Created().json(data.programador.estado(&nombre))
+        }
+        Ok(Err((status, mensaje))) => error_json(status, mensaje),
+        Err(e) => error_json(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// `PUT /api/programador/{nombre}`, reemplaza la configuración y aplica el cambio al momento
+pub async fn put_trabajo(data: web::Data<AppState>, nombre: web::Path<String>, config: web::Json<ConfigTrabajo>) -> impl Responder {
+    let nombre = nombre.into_inner();
+    let mut config = config.into_inner();
+    if config.nombre.is_empty() {
+        config.nombre = nombre.clone();
+    } else if config.nombre != nombre {
+        return error_json(StatusCode::BAD_REQUEST, "No se puede cambiar el nombre de un trabajo, cree otro".to_string());
+    }
+    let programador = data.programador.clone();
+    let nombre_bloque = nombre.clone();
+
+    match web::block(move || programador.reemplazar(&nombre_bloque, config)).await {
+        Ok(Ok(_)) => {
+            log::info!("Trabajo '{}' modificado desde la API", nombre);
+            data.programador.reprogramar(&data, &nombre);
+            HttpResponse::Ok().json(data.programador.estado(&nombre))
+        }
+        Ok(Err((status, mensaje))) => error_json(status, mensaje),
+        Err(e) => error_json(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// `DELETE /api/programador/{nombre}`
+pub async fn delete_trabajo(data: web::Data<AppState>, nombre: web::Path<String>) -> impl Responder {
+    let nombre = nombre.into_inner();
+    let programador = data.programador.clone();
+    let nombre_bloque = nombre.clone();
+
+    match web::block(move || programador.eliminar(&nombre_bloque)).await {
+        Ok(Ok(())) => {
+            log::info!("Trabajo '{}' eliminado desde la API", nombre);
+            data.programador.reprogramar(&data, &nombre);
+            HttpResponse::NoContent().finish()
+        }
+        Ok(Err((status, mensaje))) => error_json(status, mensaje),
+        Err(e) => error_json(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Query de `GET /api/programador/proximas`. Ej: `?limite=50&horas=2`
+#[derive(Debug, Deserialize)]
+pub struct ConsultaProximas {
+    limite: Option<usize>,
+    horas: Option<u32>,
+}
+
+/// `GET /api/programador/proximas`, las próximas ejecuciones de todos los trabajos habilitados
+pub async fn get_proximas(data: web::Data<AppState>, consulta: web::Query<ConsultaProximas>) -> impl Responder {
+    let limite = consulta.limite.unwrap_or(PROXIMAS_DEFECTO).clamp(1, PROXIMAS_MAX);
+    let hasta = match consulta.horas {
+        Some(horas) if horas > PROXIMAS_HORAS_MAX => {
+            return error_json(StatusCode::BAD_REQUEST, format!("horas debe ser como mucho {}", PROXIMAS_HORAS_MAX));
+        }
+        horas => horas.map(|h| Utc::now() + chrono::Duration::hours(h as i64)),
+    };
+    HttpResponse::Ok().json(data.programador.proximas(limite, hasta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Timelike};
+
+    fn siguiente(expresion: &str, desde: DateTime<Utc>) -> DateTime<Utc> {
+        parsear_expresion(expresion).unwrap().after(&desde).next().unwrap()
+    }
+
+    #[test]
+    fn cinco_campos_corren_en_el_segundo_cero() {
+        let desde = Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 30).unwrap();
+        assert_eq!(siguiente("*/15 * * * *", desde), Utc.with_ymd_and_hms(2024, 5, 10, 12, 15, 0).unwrap());
+        assert_eq!(siguiente("  0 3 * * *  ", desde), Utc.with_ymd_and_hms(2024, 5, 11, 3, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn seis_campos_respetan_los_segundos() {
+        let desde = Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+        let proxima = siguiente("30 * * * * *", desde);
+        assert_eq!((proxima.minute(), proxima.second()), (0, 30));
+        assert_eq!(siguiente("*/10 * * * * *", desde), Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 10).unwrap());
+    }
+
+    #[test]
+    fn las_expresiones_usan_la_hora_local() {
+        let proxima = super::siguiente("0 3 * * *").unwrap().with_timezone(&Local);
+        assert_eq!((proxima.hour(), proxima.minute(), proxima.second()), (3, 0, 0));
+    }
+
+    #[test]
+    fn expresiones_invalidas() {
+        for expresion in ["", "* * * *", "61 * * * *", "no es cron", "* * * * * * * * *"] {
+            let error = parsear_expresion(expresion).unwrap_err();
+            assert!(error.contains("no válida"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn proximas_respeta_limite_y_horas() {
+        let trabajo = |nombre: &str, expresion: &str, habilitado| ConfigTrabajo {
+            nombre: nombre.to_string(),
+            expresion: expresion.to_string(),
+            trabajo: Trabajo::Limpieza,
+            habilitado,
+        };
+        let programador = Programador::new(ConfigProgramador {
+            crontab: false,
+            archivo: "/nonexistent/trabajos.json".to_string(),
+            trabajos: vec![trabajo("minuto", "* * * * *", true), trabajo("apagado", "* * * * * *", false)],
+        })
+        .unwrap();
+
+        assert_eq!(programador.proximas(5, None).len(), 5);
+        let hasta = Utc::now() + chrono::Duration::hours(1);
+        let proximas = programador.proximas(PROXIMAS_MAX, Some(hasta));
+        assert!((59..=60).contains(&proximas.len()), "{}", proximas.len());
+    }
+}
@@ -0,0 +1,453 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::dashboard::ContenedorVivo;
+use crate::escenarios::FaseActiva;
+use crate::grabacion;
+use crate::servidor::imprimir_caja;
+use crate::SystemStats;
+
+// Cuántos procesos se muestran en los tops del reporte
+const TOP_PROCESOS: usize = 5;
+
+#[derive(Debug, Default)]
+struct DatosSesion {
+    cpu: Vec<f64>,
+    ram: Vec<f64>,
+    // None hasta el primer ciclo, los contenedores que ya existían no cuentan como creados
+    vistos: Option<HashSet<String>>,
+    contenedores: BTreeMap<String, ConteoCategoria>,
+    procesos: HashMap<u32, ConsumoProceso>,
+    errores: ErroresReconciliador,
+    fases: Vec<MuestrasFase>,
+}
+
+/// Las muestras tomadas durante una fase de un escenario
+#[derive(Debug)]
+struct MuestrasFase {
+    fase: FaseActiva,
+    inicio: DateTime<Utc>,
+    fin: DateTime<Utc>,
+    cpu: Vec<f64>,
+    ram: Vec<f64>,
+    max_contenedores: usize,
+}
+
+/// Acumula lo que pasa durante la ejecución para el reporte final
+pub struct Sesion {
+    inicio: DateTime<Utc>,
+    datos: Mutex<DatosSesion>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ConteoCategoria {
+    pub creados: u32,
+    pub eliminados: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumoProceso {
+    pub pid: u32,
+    pub name: String,
+    pub categoria: Option<String>,
+    pub max_cpu_percent: u8,
+    pub max_memory_rss: u64,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ErroresReconciliador {
+    pub listar: u32,
+    pub eliminar: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Estadisticas {
+    pub min: f64,
+    pub max: f64,
+    pub promedio: f64,
+    pub p95: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReporteFase {
+    pub escenario: String,
+    pub fase: String,
+    pub inicio: String,
+    pub fin: String,
+    pub muestras: usize,
+    pub cpu_porcentaje: Option<Estadisticas>,
+    pub ram_ocupada_kb: Option<Estadisticas>,
+    /// Máximo de procesos de stress vistos en una muestra
+    pub max_contenedores: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReporteSesion {
+    pub inicio: String,
+    pub fin: String,
+    pub duracion_segundos: i64,
+    pub muestras: usize,
+    pub cpu_porcentaje: Option<Estadisticas>,
+    pub ram_ocupada_kb: Option<Estadisticas>,
+    pub contenedores: BTreeMap<String, ConteoCategoria>,
+    pub top_cpu: Vec<ConsumoProceso>,
+    pub top_ram: Vec<ConsumoProceso>,
+    pub errores: ErroresReconciliador,
+    pub fases: Vec<ReporteFase>,
+}
+
+impl Sesion {
+    pub fn new() -> Self {
+        Sesion {
+            inicio: grabacion::ahora(),
+            datos: Mutex::new(DatosSesion::default()),
+        }
+    }
+
+    pub fn registrar_muestra(&self, system: &SystemStats, containers: &[ContenedorVivo], fase: Option<&FaseActiva>) {
+        let mut datos = self.datos.lock().unwrap();
+        datos.cpu.push(system.cpu_usada as f64);
+        datos.ram.push(system.ram_ocupada as f64);
+
+        if let Some(fase) = fase {
+            let ahora = grabacion::ahora();
+            // Las muestras seguidas de la misma fase de la misma corrida van juntas
+            if datos.fases.last().is_none_or(|f| &f.fase != fase) {
+                datos.fases.push(MuestrasFase {
+                    fase: fase.clone(),
+                    inicio: ahora,
+                    fin: ahora,
+                    cpu: Vec::new(),
+                    ram: Vec::new(),
+                    max_contenedores: 0,
+                });
+            }
+            let muestras = datos.fases.last_mut().unwrap();
+            muestras.fin = ahora;
+            muestras.cpu.push(system.cpu_usada as f64);
+            muestras.ram.push(system.ram_ocupada as f64);
+            let stress = containers.iter().filter(|c| c.categoria.is_some()).count();
+            muestras.max_contenedores = muestras.max_contenedores.max(stress);
+        }
+
+        for c in containers {
+            let consumo = datos.procesos.entry(c.info.pid).or_insert_with(|| ConsumoProceso {
+                pid: c.info.pid,
+                name: c.info.name.clone(),
+                categoria: c.categoria.clone(),
+                max_cpu_percent: 0,
+                max_memory_rss: 0,
+            });
+            consumo.max_cpu_percent = consumo.max_cpu_percent.max(c.info.cpu_percent);
+            consumo.max_memory_rss = consumo.max_memory_rss.max(c.info.memory_rss);
+        }
+    }
+
+    /// Recibe los contenedores de Docker de cada ciclo para contar los nuevos
+    pub fn registrar_contenedores(&self, container_categories: &HashMap<String, Vec<(String, String, i64)>>) {
+        let mut datos = self.datos.lock().unwrap();
+        let primera_vez = datos.vistos.is_none();
+        let mut nuevos = Vec::new();
+        let vistos = datos.vistos.get_or_insert_with(HashSet::new);
+
+        for (category, containers) in container_categories {
+            for (id, _, _) in containers {
+                if vistos.insert(id.clone()) && !primera_vez {
+                    nuevos.push(category.clone());
+                }
+            }
+        }
+
+        for category in nuevos {
+            datos.contenedores.entry(category).or_default().creados += 1;
+        }
+    }
+
+    pub fn registrar_eliminado(&self, categoria: &str) {
+        let mut datos = self.datos.lock().unwrap();
+        datos.contenedores.entry(categoria.to_string()).or_default().eliminados += 1;
+    }
+
+    pub fn registrar_error_listar(&self) {
+        self.datos.lock().unwrap().errores.listar += 1;
+    }
+
+    pub fn registrar_error_eliminar(&self) {
+        self.datos.lock().unwrap().errores.eliminar += 1;
+    }
+
+    pub fn reporte(&self) -> ReporteSesion {
+        let datos = self.datos.lock().unwrap();
+        let fin = grabacion::ahora();
+
+        let mut top_cpu: Vec<ConsumoProceso> = datos.procesos.values().cloned().collect();
+        top_cpu.sort_by_key(|p| std::cmp::Reverse(p.max_cpu_percent));
+        top_cpu.truncate(TOP_PROCESOS);
+
+        let mut top_ram: Vec<ConsumoProceso> = datos.procesos.values().cloned().collect();
+        top_ram.sort_by_key(|p| std::cmp::Reverse(p.max_memory_rss));
+        top_ram.truncate(TOP_PROCESOS);
+
+        ReporteSesion {
+            inicio: self.inicio.to_rfc3339(),
+            fin: fin.to_rfc3339(),
+            duracion_segundos: (fin - self.inicio).num_seconds(),
+            muestras: datos.cpu.len(),
+            cpu_porcentaje: estadisticas(&datos.cpu),
+            ram_ocupada_kb: estadisticas(&datos.ram),
+            contenedores: datos.contenedores.clone(),
+            top_cpu,
+            top_ram,
+            errores: datos.errores.clone(),
+            fases: datos
+                .fases
+                .iter()
+                .map(|f| ReporteFase {
+                    escenario: f.fase.escenario.clone(),
+                    fase: f.fase.fase.clone(),
+                    inicio: f.inicio.to_rfc3339(),
+                    fin: f.fin.to_rfc3339(),
+                    muestras: f.cpu.len(),
+                    cpu_porcentaje: estadisticas(&f.cpu),
+                    ram_ocupada_kb: estadisticas(&f.ram),
+                    max_contenedores: f.max_contenedores,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn estadisticas(valores: &[f64]) -> Option<Estadisticas> {
+    if valores.is_empty() {
+        return None;
+    }
+
+    let mut ordenados = valores.to_vec();
+    ordenados.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    // Percentil 95 por rango más cercano
+    let rango = ((0.95 * ordenados.len() as f64).ceil() as usize).max(1);
+
+    Some(Estadisticas {
+        min: ordenados[0],
+        max: ordenados[ordenados.len() - 1],
+        promedio: ordenados.iter().sum::<f64>() / ordenados.len() as f64,
+        p95: ordenados[rango - 1],
+    })
+}
+
+impl ReporteSesion {
+    pub fn imprimir(&self) {
+        imprimir_caja("REPORTE DE LA SESIÓN", &self.secciones());
+    }
+
+    /// Las secciones de la caja de `imprimir`, una línea por fila
+    fn secciones(&self) -> Vec<Vec<String>> {
+        let mut secciones = vec![
+            vec![format!("Duración: {} s   Muestras: {}", self.duracion_segundos, self.muestras)],
+            vec![
+                format!("{:<11} {:>9} {:>9} {:>9} {:>9}", "", "Mín", "Máx", "Prom", "P95"),
+                linea_estadisticas("CPU (%)", &self.cpu_porcentaje),
+                linea_estadisticas("RAM (KB)", &self.ram_ocupada_kb),
+            ],
+        ];
+
+        let mut contenedores = vec![format!("{:<11} {:>12} {:>12}", "Categoría", "Creados", "Eliminados")];
+        for (categoria, conteo) in &self.contenedores {
+            contenedores.push(format!("{:<11} {:>12} {:>12}", categoria, conteo.creados, conteo.eliminados));
+        }
+        secciones.push(contenedores);
+
+        let mut tops = vec!["Top CPU".to_string()];
+        tops.extend(self.top_cpu.iter().map(|p| format!(" {:>7} {:<20} {:>10}%", p.pid, p.name, p.max_cpu_percent)));
+        tops.push("Top RAM".to_string());
+        tops.extend(self.top_ram.iter().map(|p| format!(" {:>7} {:<20} {:>10} KB", p.pid, p.name, p.max_memory_rss)));
+        secciones.push(tops);
+
+        if !self.fases.is_empty() {
+            let mut fases = vec![format!("{:<17} {:>6} {:>8} {:>8} {:>10}", "Fase", "Muest.", "CPU prom", "CPU p95", "RAM prom")];
+            for f in &self.fases {
+                let cpu = f.cpu_porcentaje.as_ref();
+                fases.push(format!(
+                    "{:<17} {:>6} {:>8} {:>8} {:>10}",
+                    f.fase,
+                    f.muestras,
+                    cpu.map_or("-".to_string(), |e| format!("{:.1}", e.promedio)),
+                    cpu.map_or("-".to_string(), |e| format!("{:.0}", e.p95)),
+                    f.ram_ocupada_kb.as_ref().map_or("-".to_string(), |e| format!("{:.0}", e.promedio)),
+                ));
+            }
+            secciones.push(fases);
+        }
+
+        secciones.push(vec![format!("Errores al listar: {}   Errores al eliminar: {}", self.errores.listar, self.errores.eliminar)]);
+        secciones
+    }
+
+    pub fn markdown(&self) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# Reporte de la sesión\n");
+        let _ = writeln!(md, "- Inicio: {}", self.inicio);
+        let _ = writeln!(md, "- Fin: {}", self.fin);
+        let _ = writeln!(md, "- Duración: {} s", self.duracion_segundos);
+        let _ = writeln!(md, "- Muestras: {}\n", self.muestras);
+
+        let _ = writeln!(md, "## Recursos\n");
+        let _ = writeln!(md, "| Métrica | Mín | Máx | Promedio | P95 |");
+        let _ = writeln!(md, "|---|---|---|---|---|");
+        for (nombre, est) in [("CPU (%)", &self.cpu_porcentaje), ("RAM ocupada (KB)", &self.ram_ocupada_kb)] {
+            match est {
+                Some(e) => { let _ = writeln!(md, "| {} | {:.0} | {:.0} | {:.1} | {:.0} |", nombre, e.min, e.max, e.promedio, e.p95); }
+                None => { let _ = writeln!(md, "| {} | - | - | - | - |", nombre); }
+            }
+        }
+
+        let _ = writeln!(md, "\n## Contenedores por categoría\n");
+        let _ = writeln!(md, "| Categoría | Creados | Eliminados |");
+        let _ = writeln!(md, "|---|---|---|");
+        for (categoria, conteo) in &self.contenedores {
+            let _ = writeln!(md, "| {} | {} | {} |", categoria, conteo.creados, conteo.eliminados);
+        }
+
+        let _ = writeln!(md, "\n## Procesos que más consumieron\n");
+        let _ = writeln!(md, "| Top | PID | Nombre | Categoría | CPU máx (%) | RSS máx (KB) |");
+        let _ = writeln!(md, "|---|---|---|---|---|---|");
+        for (top, procesos) in [("CPU", &self.top_cpu), ("RAM", &self.top_ram)] {
+            for p in procesos {
+                let _ = writeln!(
+                    md, "| {} | {} | {} | {} | {} | {} |",
+                    top, p.pid, p.name, p.categoria.as_deref().unwrap_or("-"), p.max_cpu_percent, p.max_memory_rss
+                );
+            }
+        }
+
+        if !self.fases.is_empty() {
+            let _ = writeln!(md, "\n## Comparación de fases\n");
+            let _ = writeln!(md, "| Escenario | Fase | Inicio | Muestras | CPU prom (%) | CPU p95 (%) | CPU máx (%) | RAM prom (KB) | RAM máx (KB) | Contenedores máx |");
+            let _ = writeln!(md, "|---|---|---|---|---|---|---|---|---|---|");
+            for f in &self.fases {
+                let cpu = f.cpu_porcentaje.as_ref();
+                let ram = f.ram_ocupada_kb.as_ref();
+                let _ = writeln!(
+                    md, "| {} | {} | {} | {} | {} | {} | {} | {} | {} | {} |",
+                    f.escenario, f.fase, f.inicio, f.muestras,
+                    cpu.map_or("-".to_string(), |e| format!("{:.1}", e.promedio)),
+                    cpu.map_or("-".to_string(), |e| format!("{:.0}", e.p95)),
+                    cpu.map_or("-".to_string(), |e| format!("{:.0}", e.max)),
+                    ram.map_or("-".to_string(), |e| format!("{:.0}", e.promedio)),
+                    ram.map_or("-".to_string(), |e| format!("{:.0}", e.max)),
+                    f.max_contenedores
+                );
+            }
+        }
+
+        let _ = writeln!(md, "\n## Errores del reconciliador\n");
+        let _ = writeln!(md, "- Al listar contenedores: {}", self.errores.listar);
+        let _ = writeln!(md, "- Al eliminar contenedores: {}", self.errores.eliminar);
+        md
+    }
+
+    /// Escribe `reporte_sesion.json` y `reporte_sesion.md` en `dir`
+    pub fn guardar(&self, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
+
+        let json_path = dir.join("reporte_sesion.json");
+        fs::write(&json_path, serde_json::to_string_pretty(self)?)?;
+
+        let md_path = dir.join("reporte_sesion.md");
+        fs::write(&md_path, self.markdown())?;
+
+        Ok(vec![json_path, md_path])
+    }
+}
+
+fn linea_estadisticas(nombre: &str, estadisticas: &Option<Estadisticas>) -> String {
+    match estadisticas {
+        Some(e) => format!("{:<11} {:>9.0} {:>9.0} {:>9.1} {:>9.0}", nombre, e.min, e.max, e.promedio, e.p95),
+        None => format!("{:<11} {:>9} {:>9} {:>9} {:>9}", nombre, "-", "-", "-", "-"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proceso(pid: u32, name: &str, cpu_percent: u8, memory_rss: u64) -> ContenedorVivo {
+        ContenedorVivo {
+            info: crate::ContainerInfo {
+                pid,
+                name: name.to_string(),
+                cmdline: String::new(),
+                memory_rss,
+                memory_percent: 1,
+                virtual_memory: 0,
+                cpu_percent,
+                disk_usage: 0,
+                io_read_bytes: 0,
+                io_write_bytes: 0,
+            },
+            categoria: None,
+        }
+    }
+
+    #[test]
+    fn estadisticas_vacias() {
+        assert!(estadisticas(&[]).is_none());
+    }
+
+    #[test]
+    fn estadisticas_de_una_muestra() {
+        let e = estadisticas(&[42.0]).unwrap();
+        assert_eq!((e.min, e.max, e.promedio, e.p95), (42.0, 42.0, 42.0, 42.0));
+    }
+
+    #[test]
+    fn p95_por_rango_mas_cercano() {
+        // 1..=100 desordenado: el rango 95 es el valor 95
+        let valores: Vec<f64> = (1..=100).rev().map(f64::from).collect();
+        let e = estadisticas(&valores).unwrap();
+        assert_eq!(e.min, 1.0);
+        assert_eq!(e.max, 100.0);
+        assert_eq!(e.promedio, 50.5);
+        assert_eq!(e.p95, 95.0);
+
+        // Con 10 muestras ceil(9.5) = 10, el p95 es el máximo
+        let valores: Vec<f64> = (1..=10).map(f64::from).collect();
+        assert_eq!(estadisticas(&valores).unwrap().p95, 10.0);
+
+        // Con 21 muestras ceil(19.95) = 20
+        let valores: Vec<f64> = (1..=21).map(f64::from).collect();
+        assert_eq!(estadisticas(&valores).unwrap().p95, 20.0);
+    }
+
+    #[test]
+    fn top_de_procesos_ordenado_de_mayor_a_menor() {
+        let sesion = Sesion::new();
+        let system = SystemStats { ram_total: 100, ram_libre: 50, ram_ocupada: 50, cpu_usada: 10 };
+        let procesos: Vec<ContenedorVivo> =
+            [(1, 5, 300), (2, 90, 100), (3, 40, 200)].into_iter().map(|(pid, cpu, rss)| proceso(pid, "stress", cpu, rss)).collect();
+        sesion.registrar_muestra(&system, &procesos, None);
+
+        let reporte = sesion.reporte();
+        assert_eq!(reporte.top_cpu.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![2, 3, 1]);
+        assert_eq!(reporte.top_ram.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn las_secciones_no_recortan_nombres_largos() {
+        let sesion = Sesion::new();
+        let system = SystemStats { ram_total: 100, ram_libre: 50, ram_ocupada: 50, cpu_usada: 10 };
+        let nombre = "un-proceso-con-un-nombre-bastante-mas-largo-que-la-caja";
+        sesion.registrar_muestra(&system, &[proceso(7, nombre, 1, 1)], None);
+
+        let secciones = sesion.reporte().secciones();
+        let tops = &secciones[3];
+        assert_eq!(tops.iter().filter(|l| l.contains(nombre)).count(), 2);
+        assert!(secciones.last().unwrap()[0].starts_with("Errores al listar: 0"));
+    }
+}
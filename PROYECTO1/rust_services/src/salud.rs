@@ -0,0 +1,182 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{config, leer_sysinfo, AppState};
+
+// Con 3 ciclos seguidos del monitor sin éxito el servicio deja de estar listo
+const CICLOS_SIN_EXITO: u64 = 3;
+// Si el ciclo del monitor no avanza en este número de intervalos se considera trabado
+const CICLOS_SIN_LATIDO: u64 = 12;
+const TIMEOUT_DOCKER: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Default)]
+struct DatosSalud {
+    ultimo_latido: Option<u64>,
+    ultima_muestra_ok: Option<u64>,
+    error_muestra: Option<String>,
+    ultima_reconciliacion_ok: Option<u64>,
+    error_reconciliacion: Option<String>,
+}
+
+/// Lo que el monitor va reportando de cada ciclo, para `/healthz` y `/readyz`
+pub struct EstadoSalud {
+    inicio: u64,
+    datos: Mutex<DatosSalud>,
+}
+
+#[derive(Debug, Serialize)]
+struct Chequeo {
+    ok: bool,
+    detalle: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ultimo_ok: Option<u64>,
+}
+
+fn max_antiguedad_segundos() -> u64 {
+    CICLOS_SIN_EXITO * config::actual().monitor.intervalo_segundos
+}
+
+fn max_sin_latido_segundos() -> u64 {
+    CICLOS_SIN_LATIDO * config::actual().monitor.intervalo_segundos
+}
+
+fn ahora() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+impl EstadoSalud {
+    pub fn new() -> Self {
+        EstadoSalud {
+            inicio: ahora(),
+            datos: Mutex::new(DatosSalud::default()),
+        }
+    }
+
+    /// Se llama al empezar cada ciclo del monitor
+    pub fn latido(&self) {
+        self.datos.lock().unwrap().ultimo_latido = Some(ahora());
+    }
+
+    pub fn registrar_muestra(&self, resultado: Result<(), String>) {
+        let mut datos = self.datos.lock().unwrap();
+        match resultado {
+            Ok(()) => {
+                datos.ultima_muestra_ok = Some(ahora());
+                datos.error_muestra = None;
+            }
+            Err(e) => datos.error_muestra = Some(e),
+        }
+    }
+
+    pub fn registrar_reconciliacion(&self, resultado: Result<(), String>) {
+        let mut datos = self.datos.lock().unwrap();
+        match resultado {
+            Ok(()) => {
+                datos.ultima_reconciliacion_ok = Some(ahora());
+                datos.error_reconciliacion = None;
+            }
+            Err(e) => datos.error_reconciliacion = Some(e),
+        }
+    }
+}
+
+/// Revisa que un evento del monitor sea reciente
+fn chequeo_reciente(ultimo_ok: Option<u64>, error: &Option<String>, que: &str) -> Chequeo {
+    let (ok, detalle) = match (ultimo_ok, error) {
+        (_, Some(e)) => (false, format!("Falló la última {}: {}", que, e)),
+        (None, None) => (false, format!("Todavía no hay una {} completa", que)),
+        (Some(t), None) => {
+            let antiguedad = ahora().saturating_sub(t);
+            if antiguedad > max_antiguedad_segundos() {
+                (false, format!("La última {} fue hace {} s", que, antiguedad))
+            } else {
+                (true, format!("Última {} hace {} s", que, antiguedad))
+            }
+        }
+    };
+    Chequeo { ok, detalle, ultimo_ok }
+}
+
+/// `GET /healthz`, el proceso responde y el ciclo del monitor sigue avanzando
+pub async fn healthz(data: web::Data<AppState>) -> impl Responder {
+    let salud = &data.salud;
+    let ultimo_latido = salud.datos.lock().unwrap().ultimo_latido;
+    let desde = ultimo_latido.unwrap_or(salud.inicio);
+    let vivo = ahora().saturating_sub(desde) <= max_sin_latido_segundos();
+
+    let cuerpo = json!({
+        "estado": if vivo { "vivo" } else { "trabado" },
+        "uptime_segundos": ahora().saturating_sub(salud.inicio),
+        "ultimo_latido_monitor": ultimo_latido,
+    });
+
+    if vivo {
+        HttpResponse::Ok().json(cuerpo)
+    } else {
+        HttpResponse::ServiceUnavailable().json(cuerpo)
+    }
+}
+
+/// `GET /readyz`, revisa cada dependencia y responde 503 si alguna falla
+pub async fn readyz(data: web::Data<AppState>) -> impl Responder {
+    let mut chequeos = BTreeMap::new();
+
+    let docker = match tokio::time::timeout(TIMEOUT_DOCKER, data.docker.ping()).await {
+        Ok(Ok(_)) => Chequeo { ok: true, detalle: "La API de Docker responde".to_string(), ultimo_ok: None },
+        Ok(Err(e)) => Chequeo { ok: false, detalle: format!("Error al comunicarse con Docker: {}", e), ultimo_ok: None },
+        Err(_) => Chequeo { ok: false, detalle: format!("Docker no respondió en {:?}", TIMEOUT_DOCKER), ultimo_ok: None },
+    };
+    chequeos.insert("docker", docker);
+
+    // El archivo del módulo de kernel se lee de nuevo ahora, además de ver si el monitor lo leyó hace poco
+    let lectura = web::block(|| leer_sysinfo().map(|_| ()).map_err(|e| e.to_string())).await;
+    let proc_ahora = match lectura {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e),
+        Err(e) => Some(e.to_string()),
+    };
+
+    {
+        let datos = data.salud.datos.lock().unwrap();
+        let mut proc = chequeo_reciente(datos.ultima_muestra_ok, &datos.error_muestra, "lectura del módulo de kernel");
+        if let Some(e) = proc_ahora {
+            proc.ok = false;
+            proc.detalle = format!("No se pudo leer ni parsear el archivo del módulo: {}", e);
+        }
+        chequeos.insert("proc", proc);
+        chequeos.insert(
+            "reconciliacion",
+            chequeo_reciente(datos.ultima_reconciliacion_ok, &datos.error_reconciliacion, "reconciliación"),
+        );
+    }
+
+    let escritor = data.escritor_logs.estado();
+    let logs = match (&escritor.ultimo_error, escritor.en_cola >= escritor.capacidad_cola) {
+        _ if !escritor.escritor_activo => Chequeo {
+            ok: false,
+            detalle: format!("El escritor de logs no está corriendo, {} descartados", escritor.descartados_escritor_cerrado),
+            ultimo_ok: escritor.ultima_escritura,
+        },
+        (Some(e), _) => Chequeo { ok: false, detalle: format!("Error al escribir logs: {}", e), ultimo_ok: escritor.ultima_escritura },
+        (None, true) => Chequeo { ok: false, detalle: "La cola de logs está llena".to_string(), ultimo_ok: escritor.ultima_escritura },
+        (None, false) => Chequeo {
+            ok: true,
+            detalle: format!("{} en cola, {} descartados", escritor.en_cola, escritor.descartados_cola_llena + escritor.descartados_error + escritor.descartados_escritor_cerrado),
+            ultimo_ok: escritor.ultima_escritura,
+        },
+    };
+    chequeos.insert("escritor_logs", logs);
+
+    let listo = chequeos.values().all(|c| c.ok);
+    let cuerpo = json!({ "estado": if listo { "listo" } else { "no_listo" }, "chequeos": chequeos });
+
+    if listo {
+        HttpResponse::Ok().json(cuerpo)
+    } else {
+        HttpResponse::ServiceUnavailable().json(cuerpo)
+    }
+}
@@ -0,0 +1,829 @@
+//! `serve` y `monitor`: el ciclo del monitor, el servidor HTTP y lo que se hace al cerrar
+
+use actix_web::{web, App, HttpServer, HttpResponse, Responder, middleware, HttpRequest};
+use actix_web::dev::Service; // Necesario para usar call()
+use std::sync::Arc;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use bollard::container::RemoveContainerOptions;
+use std::collections::HashMap;
+use tokio::time::{sleep, Duration};
+use std::fs;
+use std::error::Error;
+use serde_json::json;
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::path::Path;
+use actix_files::Files;  // Importar actix_files
+
+use crate::alertas::{MotorAlertas, MuestraAlertas};
+use crate::dashboard::{self, Instantanea};
+use crate::escenarios::{self, FaseActiva};
+use crate::eventos::{self, BusEventos, EventoMonitor};
+use crate::gestor_crontab::{Cambio, EstadoBloque, GestorCrontab};
+use crate::reporte::Sesion;
+use crate::{api, auth, cierre, config, escritor_logs, grabacion, graficas, limite, logs, metricas, programador, salud, stress, tls};
+use crate::{enriquecer_contenedores, enviar_container_logs, leer_sysinfo, leer_sysinfo_monitor, clasificar_contenedor, AppState, ContainerLog, RequestLog, SystemInfo};
+
+async fn index(req: HttpRequest) -> impl Responder {
+    let ip = req.connection_info().peer_addr().unwrap_or("unknown").to_string();
+    
+    let log = RequestLog {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        method: req.method().to_string(),
+        path: req.path().to_string(),
+        status: 200,
+        ip,
+    };
+    
+    log::info!("Request recibida: {:?}", log);
+    HttpResponse::Ok().body("¡Servicio funcionando!")
+}
+
+fn request_log(req: &HttpRequest, status: u16) -> RequestLog {
+    RequestLog {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        method: req.method().to_string(),
+        path: req.path().to_string(),
+        status,
+        ip: req.connection_info().peer_addr().unwrap_or("unknown").to_string(),
+    }
+}
+
+fn start_logger_container() -> String {
+    let logger = &config::actual().logger;
+    let volumen = format!("{}:/logs", logger.volumen);
+    let output = Command::new("docker")
+        .args(["run", "-d", "--name", &logger.nombre, "-v", &volumen, &logger.imagen, "sh", "-c", "touch /logs/requests.log && touch /logs/container_logs.json && tail -f /dev/null"])
+        .output()
+        .expect("No se pudo crear el contenedor logger");
+    
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+async fn gestionar_contenedores(app: web::Data<AppState>) {
+    let intervalo = Duration::from_secs(config::actual().monitor.intervalo_segundos);
+    // Solo se revisa entre ciclos, así un cierre nunca deja cpu.json o ram.json a medio escribir
+    while !app.cierre.iniciado() {
+        app.salud.latido();
+        let resultado = gestionar_contenedores_por_categoria(&app).await.map_err(|e| e.to_string());
+        if let Err(e) = &resultado {
+            log::error!("Error en el ciclo del monitor: {}", e);
+        }
+        app.salud.registrar_reconciliacion(resultado);
+        tokio::select! {
+            sigue = grabacion::esperar_ciclo(intervalo) => if !sigue { break },
+            _ = app.cierre.esperar() => break,
+        }
+    }
+
+    if let Some(resumen) = grabacion::resumen() {
+        log::info!("{}", resumen);
+        println!("{}", resumen);
+    }
+}
+
+async fn gestionar_contenedores_por_categoria(app: &AppState) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let docker = &app.docker;
+    let logger_container_id = app.logger_container_id.as_str();
+    let (monitor, eventos, alertas, sesion) = (&app.monitor, &app.eventos, &app.alertas, &app.sesion);
+
+    // Leer información del sistema
+    // Si hay un escenario corriendo, cada muestra se etiqueta con su fase
+    let fase = app.escenarios.fase_activa();
+
+    let sys_info = match leer_sysinfo_monitor() {
+        Ok(sys_info) => {
+            app.salud.registrar_muestra(Ok(()));
+            Some(sys_info)
+        }
+        Err(e) => {
+            log::error!("No se pudo leer la información del sistema: {}", e);
+            app.salud.registrar_muestra(Err(e.to_string()));
+            None
+        }
+    };
+
+    if let Some(sys_info) = &sys_info {
+        log::info!("=== Información del Sistema ===");
+        log::info!("RAM Total: {} KB", sys_info.system.ram_total);
+        log::info!("RAM Libre: {} KB", sys_info.system.ram_libre);
+        log::info!("RAM Ocupada: {} KB", sys_info.system.ram_ocupada);
+        log::info!("CPU Usada: {}%", sys_info.system.cpu_usada);
+        let cpu_json = sys_info.system.cpu_usada;
+        let ram_json = sys_info.system.ram_ocupada;
+        let _ = guardar_cpu_info(cpu_json, fase.as_ref());
+        let _ = guardar_ram_info(ram_json, fase.as_ref());
+
+        let containers = enriquecer_contenedores(sys_info.containers.clone());
+        if let Err(e) = metricas::guardar_contenedores(&config::actual().rutas.metricas_contenedores(), &containers, fase.as_ref()) {
+            log::error!("No se pudieron guardar las métricas de los contenedores: {}", e);
+        }
+        sesion.registrar_muestra(&sys_info.system, &containers, fase.as_ref());
+        eventos.publicar(EventoMonitor::Muestra {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            system: sys_info.system.clone(),
+            containers,
+            fase: fase.clone(),
+        });
+        
+        // Imprimir en consola de manera estilizada
+        imprimir_caja("INFORMACIÓN DEL SISTEMA", &[vec![
+            format!("RAM Total:   {:>10} KB", sys_info.system.ram_total),
+            format!("RAM Libre:   {:>10} KB", sys_info.system.ram_libre),
+            format!("RAM Ocupada: {:>10} KB", sys_info.system.ram_ocupada),
+            format!("CPU Usada:   {:>10}%", sys_info.system.cpu_usada),
+        ]]);
+    }
+
+    let containers = match grabacion::contenedores(docker, true).await {
+        Ok(containers) => containers,
+        Err(e) => {
+            sesion.registrar_error_listar();
+            // Aunque Docker falle se publica lo que se leyó del kernel
+            evaluar_alertas(alertas, eventos, sys_info.as_ref(), None);
+            monitor.publicar(construir_instantanea(sys_info, HashMap::new(), Vec::new()));
+            return Err(e.into());
+        }
+    };
+    
+    // Group containers by category
+    let mut container_categories: HashMap<String, Vec<(String, String, i64)>> = HashMap::new();
+    
+    for container in containers {
+        if let (Some(names), Some(id), Some(created)) = (&container.names, &container.id, container.created) {
+            for name in names {
+                if let Some(category) = clasificar_contenedor(name) {
+                    // Store id, name and creation time
+                    container_categories.entry(category).or_default().push((id.clone(), name.clone(), created));
+                }
+            }
+        }
+    }
+    
+    let _ = guardar_conteo_categorias(&container_categories);
+    sesion.registrar_contenedores(&container_categories);
+
+    // Keep only the newest container of each type
+    let mut removed_containers = Vec::new();
+    
+    for (category, containers) in container_categories.iter_mut() {
+        let (conservados, sobrantes) =
+            separar_sobrantes(std::mem::take(containers), |id| id == logger_container_id || app.protegido(id));
+
+        for (id, name, _) in sobrantes {
+            log::info!("Eliminando contenedor de categoria {}: {} ({})", category, name, id);
+            
+            // Log container deletion with timestamp
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+            
+            let container_log = ContainerLog {
+                timestamp,
+                category: category.clone(),
+                name: name.clone(),
+                action: "eliminado".to_string(),
+            };
+            
+            removed_containers.push(container_log);
+            
+            let resultado = grabacion::eliminar(docker, &id, true).await;
+
+            match &resultado {
+                Ok(_) => sesion.registrar_eliminado(category),
+                Err(_) => sesion.registrar_error_eliminar(),
+            }
+
+            eventos.publicar(EventoMonitor::Decision {
+                timestamp,
+                categoria: category.clone(),
+                id: id.clone(),
+                nombre: name.clone(),
+                accion: "eliminar".to_string(),
+                error: resultado.err().map(|e| e.to_string()),
+            });
+        }
+
+        for (id, name, _) in &conservados {
+            eventos.publicar(EventoMonitor::Decision {
+                timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+                categoria: category.clone(),
+                id: id.clone(),
+                nombre: name.clone(),
+                accion: "conservar".to_string(),
+                error: None,
+            });
+        }
+        *containers = conservados;
+    }
+    
+    // Print grouped containers
+    let secciones: Vec<Vec<String>> = container_categories
+        .iter()
+        .map(|(category, containers)| {
+            let mut lineas = vec![format!("Categoría: {}", category)];
+            for (id, name, created) in containers {
+                let created_str = chrono::DateTime::from_timestamp(*created, 0)
+                    .map(|fecha| fecha.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_else(|| created.to_string());
+                lineas.push(format!(" - {}: {} (Creado: {})", id.get(..12).unwrap_or(id), name.trim_start_matches('/'), created_str));
+            }
+            lineas
+        })
+        .collect();
+    imprimir_caja("CONTENEDORES ACTIVOS POR CATEGORÍA", &secciones);
+
+    let categorias: HashMap<String, usize> = container_categories.iter().map(|(c, ids)| (c.clone(), ids.len())).collect();
+    evaluar_alertas(alertas, eventos, sys_info.as_ref(), Some(&categorias));
+    monitor.publicar(construir_instantanea(sys_info, categorias, removed_containers.clone()));
+    
+    // Log removed containers
+    if !removed_containers.is_empty() {
+        let lineas: Vec<String> = removed_containers
+            .iter()
+            .map(|log| format!("{} - {} - {}", log.category, log.name.trim_start_matches('/'), log.timestamp))
+            .collect();
+        imprimir_caja("CONTENEDORES ELIMINADOS", &[lineas]);
+        
+        // Send logs to the logger container
+        enviar_container_logs(&removed_containers)?;
+    }
+    
+    Ok(())
+}
+
+
+/// Dibuja una caja con un título y secciones separadas, el ancho se ajusta a la línea más larga
+/// para que los bordes no se desalineen con nombres o valores largos
+pub(crate) fn imprimir_caja(titulo: &str, secciones: &[Vec<String>]) {
+    let ancho = secciones
+        .iter()
+        .flatten()
+        .map(|linea| linea.chars().count())
+        .chain([titulo.chars().count(), 39])
+        .max()
+        .unwrap_or(0);
+    let borde = "═".repeat(ancho + 2);
+
+    println!("╔{}╗", borde);
+    println!("║ {:^ancho$} ║", titulo);
+    for seccion in secciones {
+        println!("╠{}╣", borde);
+        for linea in seccion {
+            println!("║ {:<ancho$} ║", linea);
+        }
+    }
+    println!("╚{}╝\n", borde);
+}
+
+// Id, nombre y fecha de creación
+type ContenedorCategoria = (String, String, i64);
+
+/// Separa los contenedores de una categoría en los que quedan y los que se eliminan. Los protegidos
+/// (logger, escenario en curso, `/api/stress`) siempre quedan y no cuentan al elegir el más nuevo.
+fn separar_sobrantes(containers: Vec<ContenedorCategoria>, protegido: impl Fn(&str) -> bool) -> (Vec<ContenedorCategoria>, Vec<ContenedorCategoria>) {
+    let (mut conservados, mut candidatos): (Vec<_>, Vec<_>) = containers.into_iter().partition(|(id, _, _)| protegido(id));
+
+    // El más nuevo primero, ese es el que queda
+    candidatos.sort_by_key(|c| std::cmp::Reverse(c.2));
+    let sobrantes = candidatos.split_off(candidatos.len().min(1));
+    conservados.extend(candidatos);
+    (conservados, sobrantes)
+}
+
+fn evaluar_alertas(alertas: &MotorAlertas, eventos: &BusEventos, sys_info: Option<&SystemInfo>, categorias: Option<&HashMap<String, usize>>) {
+    let muestra = MuestraAlertas {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        system: sys_info.map(|info| &info.system),
+        categorias,
+    };
+
+    for notificacion in alertas.evaluar(&muestra) {
+        alertas.notificar(&notificacion);
+        eventos.publicar(EventoMonitor::Alerta(notificacion));
+    }
+}
+
+
+fn construir_instantanea(sys_info: Option<SystemInfo>, categorias: HashMap<String, usize>, eventos: Vec<ContainerLog>) -> Instantanea {
+    let (system, containers) = match sys_info {
+        Some(info) => (Some(info.system), info.containers),
+        None => (None, Vec::new()),
+    };
+
+    Instantanea {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        system,
+        containers: enriquecer_contenedores(containers),
+        categorias,
+        eventos,
+    }
+}
+
+
+fn imprimir_estado_final(sesion: &Sesion) {
+    if let Ok(sys_info) = leer_sysinfo() {
+        imprimir_caja("INFORMACIÓN FINAL DEL SISTEMA", &[vec![
+            format!("RAM Total:   {:>10} KB", sys_info.system.ram_total),
+            format!("RAM Libre:   {:>10} KB", sys_info.system.ram_libre),
+            format!("RAM Ocupada: {:>10} KB", sys_info.system.ram_ocupada),
+            format!("CPU Usada:   {:>10}%", sys_info.system.cpu_usada),
+        ]]);
+    }
+
+    // Reporte de toda la sesión, se guarda junto a cpu.json y ram.json
+    let reporte = sesion.reporte();
+    reporte.imprimir();
+
+    match reporte.guardar(Path::new(&config::actual().rutas.graficas)) {
+        Ok(archivos) => println!("Reporte guardado en {:?}", archivos),
+        Err(e) => eprintln!("Error al guardar el reporte de la sesión: {}", e),
+    }
+}
+
+// Directorio donde se escriben las gráficas, `rutas.graficas_salida`
+fn directorio_graficas() -> String {
+    config::actual().rutas.graficas_salida.clone()
+}
+
+fn generar_graficas(dir_salida: &str) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+    let rutas = &config::actual().rutas;
+    graficas::generar_graficas(&rutas.cpu_json(), &rutas.ram_json(), &rutas.conteo_json(), Path::new(dir_salida))
+}
+
+async fn generate_graphs() -> impl Responder {
+    let dir_salida = directorio_graficas();
+    let resultado = web::block(move || generar_graficas(&dir_salida)).await;
+
+    match resultado {
+        Ok(Ok(archivos)) => {
+            log::info!("Gráficas generadas: {:?}", archivos);
+            let urls: Vec<String> = archivos.iter().map(|a| format!("/graficas/{}", a)).collect();
+            HttpResponse::Ok().json(json!({
+                "mensaje": "Gráficas generadas correctamente",
+                "archivos": urls,
+            }))
+        }
+        Ok(Err(e)) => {
+            log::error!("Error al generar las gráficas: {}", e);
+            HttpResponse::InternalServerError().body(format!("Error al generar las gráficas: {}", e))
+        }
+        Err(e) => {
+            log::error!("Error al generar las gráficas: {}", e);
+            HttpResponse::InternalServerError().body("Error al generar las gráficas")
+        }
+    }
+}
+
+fn configurar_cronjob(gestor: &GestorCrontab) {
+    match gestor.instalar() {
+        Ok(Cambio::SinCambios) => println!("Cronjob ya estaba configurado"),
+        Ok(Cambio::Reparado) => println!("Cronjob reparado, el bloque en crontab no coincidía"),
+        Ok(_) => println!("Cronjob configurado correctamente"),
+        Err(e) => eprintln!("Error al configurar el cronjob: {}", e),
+    }
+}
+
+fn eliminar_cronjob(gestor: &GestorCrontab) {
+    // Solo se quita nuestro bloque, el resto del crontab del usuario queda igual
+    match gestor.desinstalar() {
+        Ok(Cambio::SinCambios) => println!("No había cronjob que eliminar"),
+        Ok(_) => println!("Cronjob eliminado correctamente"),
+        Err(e) => eprintln!("Error al eliminar el cronjob: {}", e),
+    }
+}
+
+// Si alguien edita o borra el bloque mientras el servicio corre, se vuelve a dejar como debe
+async fn vigilar_crontab(gestor: Arc<GestorCrontab>) {
+    loop {
+        sleep(Duration::from_secs(60)).await;
+        let gestor = gestor.clone();
+        let resultado = web::block(move || -> Result<Option<EstadoBloque>, Box<dyn Error + Send + Sync>> {
+            match gestor.revisar()? {
+                EstadoBloque::AlDia => Ok(None),
+                estado => {
+                    gestor.instalar()?;
+                    Ok(Some(estado))
+                }
+            }
+        })
+        .await;
+
+        match resultado {
+            Ok(Ok(None)) => {}
+            Ok(Ok(Some(estado))) => log::warn!("El bloque del crontab estaba {:?}, se volvió a instalar", estado),
+            Ok(Err(e)) => log::error!("No se pudo revisar el crontab: {}", e),
+            Err(e) => log::error!("No se pudo revisar el crontab: {}", e),
+        }
+    }
+}
+
+fn guardar_cpu_info(cpu_usada: u8, fase: Option<&FaseActiva>) -> std::io::Result<()> {
+    let file_path = config::actual().rutas.cpu_json();
+    let path = Path::new(&file_path);
+
+    // Crear directorio si no existe
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut data = vec![];
+
+    // Leer archivo si existe
+    if path.exists() {
+        let mut file = fs::File::open(&file_path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        
+        if !contents.trim().is_empty() {
+            data = serde_json::from_str(&contents).unwrap_or_else(|_| Vec::new());
+        }
+    }
+
+    // Agregar nuevo registro
+    let mut nuevo_registro = json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "cpu_usada": cpu_usada
+    });
+    etiquetar_fase(&mut nuevo_registro, fase);
+    data.push(nuevo_registro);
+
+    // Escribir datos actualizados en el archivo
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true) // Se debe sobrescribir el archivo
+        .open(&file_path)?;
+    
+    let json_data = serde_json::to_string_pretty(&data)?;
+    file.write_all(json_data.as_bytes())?;
+    
+    Ok(())
+}
+
+fn guardar_ram_info(ram_ocupada: u64, fase: Option<&FaseActiva>) -> std::io::Result<()> {
+    let file_path = config::actual().rutas.ram_json();
+    let path = Path::new(&file_path);
+
+    // Crear directorio si no existe
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut data = vec![];
+
+    // Leer archivo si existe
+    if path.exists() {
+        let mut file = fs::File::open(&file_path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        
+        if !contents.trim().is_empty() {
+            data = serde_json::from_str(&contents).unwrap_or_else(|_| Vec::new());
+        }
+    }
+
+    // Agregar nuevo registro
+    let mut nuevo_registro = json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "ram_ocupada": ram_ocupada
+    });
+    etiquetar_fase(&mut nuevo_registro, fase);
+    data.push(nuevo_registro);
+
+    // Escribir datos actualizados en el archivo
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true) // Se debe sobrescribir el archivo
+        .open(&file_path)?;
+    
+    let json_data = serde_json::to_string_pretty(&data)?;
+    file.write_all(json_data.as_bytes())?;
+    
+    Ok(())
+}
+
+// Agrega el escenario y la fase al registro, sin escenario el registro queda como antes
+fn etiquetar_fase(registro: &mut serde_json::Value, fase: Option<&FaseActiva>) {
+    if let Some(fase) = fase {
+        registro["escenario"] = json!(fase.escenario);
+        registro["fase"] = json!(fase.fase);
+    }
+}
+
+fn guardar_conteo_categorias(container_categories: &HashMap<String, Vec<(String, String, i64)>>) -> std::io::Result<()> {
+    let file_path = config::actual().rutas.conteo_json();
+    let path = Path::new(&file_path);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut data: Vec<serde_json::Value> = vec![];
+
+    if path.exists() {
+        let contents = fs::read_to_string(path)?;
+        if !contents.trim().is_empty() {
+            data = serde_json::from_str(&contents).unwrap_or_else(|_| Vec::new());
+        }
+    }
+
+    // Se registran todas las categorías, aunque no tengan contenedores
+    let mut categorias: HashMap<&str, usize> = stress::CATEGORIAS.iter().map(|c| (*c, 0)).collect();
+    for (category, containers) in container_categories {
+        categorias.insert(category.as_str(), containers.len());
+    }
+
+    data.push(json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "categorias": categorias
+    }));
+
+    let json_data = serde_json::to_string_pretty(&data)?;
+    fs::write(path, json_data)?;
+
+    Ok(())
+}
+
+/// Subcomando `serve`: servidor HTTP, monitor, programador y crontab
+pub async fn servir() -> Result<(), Box<dyn Error>> {
+    log::info!("Iniciando servicio...");
+    let senales = cierre::escuchar_senales()?;
+
+    let config = config::actual().clone();
+    let tls = tls::configurar(&config.tls).map_err(|e| format!("Configuración TLS inválida: {}", e))?;
+
+    // Al reproducir una grabación no se toca el crontab ni Docker
+    let reproduciendo = grabacion::reproduciendo();
+
+    // Configurar el cronjob, o quitarlo si la carga la genera el programador interno
+    let gestor_crontab = Arc::new(GestorCrontab::para_script(&config.rutas.cron_script));
+    let usar_crontab = config.programador.crontab && !reproduciendo;
+    if usar_crontab {
+        configurar_cronjob(&gestor_crontab);
+    } else if !reproduciendo {
+        eliminar_cronjob(&gestor_crontab);
+    }
+
+    // Configura y ejecuta el contenedor de logs
+    let container_id = if reproduciendo { String::new() } else { start_logger_container() };
+    let direccion = (config.servidor.direccion.clone(), config.servidor.puerto);
+    let dir_dashboard = config.rutas.dashboard.clone();
+    let app_state = web::Data::new(AppState::new(config, container_id)?);
+    let max_body_bytes = app_state.limitador.max_body_bytes();
+    let escritor_logs = app_state.escritor_logs.clone();
+
+    // Iniciar el monitoreo de contenedores
+    let monitor = tokio::spawn(gestionar_contenedores(app_state.clone()));
+    if usar_crontab {
+        tokio::spawn(vigilar_crontab(gestor_crontab.clone()));
+    }
+    // Al reproducir los trabajos no corren, generar_carga lanzaría contenedores de verdad
+    if !reproduciendo {
+        app_state.programador.iniciar(app_state.clone());
+    }
+    let app_cierre = app_state.clone();
+
+    // Directorio de gráficas generadas, se sirve en /graficas
+    let dir_graficas = directorio_graficas();
+    fs::create_dir_all(&dir_graficas)?;
+
+    // Iniciar el servidor HTTP
+    let servidor = HttpServer::new(move || {
+        let escritor = escritor_logs.clone();
+
+        App::new()
+            .app_data(app_state.clone())
+            .app_data(web::JsonConfig::default().limit(max_body_bytes))
+            .app_data(web::PayloadConfig::new(max_body_bytes))
+            // Autenticación por token y límite de peticiones, quedan dentro del registro de peticiones
+            .wrap(middleware::from_fn(auth::verificar))
+            .wrap(middleware::from_fn(limite::limitar))
+            .wrap(middleware::Logger::default())
+            .wrap_fn(move |req, srv| {
+                // Registrar la petición después de procesarla, sin esperar a que se escriba
+                let escritor = escritor.clone();
+                let fut = srv.call(req);
+
+                async move {
+                    let res = fut.await?;
+                    escritor.registrar(request_log(res.request(), res.status().as_u16()));
+                    Ok(res)
+                }
+            })
+            .service(web::resource("/").to(index))
+            // Liveness y readiness
+            .service(web::resource("/healthz").route(web::get().to(salud::healthz)))
+            .service(web::resource("/readyz").route(web::get().to(salud::readyz)))
+            .service(web::resource("/logs").route(web::get().to(logs::get_logs))) // Ruta para obtener logs
+            .service(web::resource("/logs/escritor").route(web::get().to(escritor_logs::get_estado_escritor)))
+            .service(web::resource("/logs/generate_graphs").route(web::post().to(generate_graphs))) // Ruta para generar gráficas
+            .service(Files::new("/graficas", &dir_graficas).show_files_listing())
+            // Dashboard en vivo, las rutas de datos van antes que los archivos estáticos
+            .service(web::resource("/dashboard/estado").route(web::get().to(dashboard::dashboard_estado)))
+            .service(web::resource("/dashboard/eventos").route(web::get().to(dashboard::dashboard_eventos)))
+            .service(Files::new("/dashboard", &dir_dashboard).index_file("index.html"))
+            // Stream de muestras y decisiones del monitor
+            .service(web::resource("/stream").route(web::get().to(eventos::stream_sse)))
+            .service(web::resource("/stream/ws").route(web::get().to(eventos::stream_ws)))
+            // API REST con el estado actual del sistema y los contenedores
+            .service(web::resource("/api/system").route(web::get().to(api::get_system)))
+            .service(web::resource("/api/containers").route(web::get().to(api::get_containers)))
+            .service(web::resource("/api/containers/{id}").route(web::delete().to(api::delete_container)))
+            .service(web::resource("/api/containers/{id}/{accion}").route(web::post().to(api::controlar_contenedor)))
+            .service(web::resource("/api/stress").route(web::post().to(api::post_stress)))
+            .service(
+                web::resource("/api/programador")
+                    .route(web::get().to(programador::get_trabajos))
+                    .route(web::post().to(programador::post_trabajo)),
+            )
+            .service(web::resource("/api/programador/proximas").route(web::get().to(programador::get_proximas)))
+            .service(
+                web::resource("/api/programador/{nombre}")
+                    .route(web::get().to(programador::get_trabajo))
+                    .route(web::put().to(programador::put_trabajo))
+                    .route(web::delete().to(programador::delete_trabajo)),
+            )
+            .service(web::resource("/api/escenarios").route(web::get().to(escenarios::get_escenarios)))
+            .service(web::resource("/api/escenarios/detener").route(web::post().to(escenarios::detener_escenario)))
+            .service(web::resource("/api/escenarios/{nombre}/iniciar").route(web::post().to(escenarios::iniciar_escenario)))
+            .service(web::resource("/api/metrics").route(web::get().to(metricas::get_metrics)))
+    })
+    // Las señales las atiende `cerrar`, si actix también las atiende el proceso
+    // termina antes de que se escriban el reporte y las gráficas finales
+    .disable_signals()
+    .shutdown_timeout(cierre::ESPERA_HTTP.as_secs());
+
+    let servidor = match tls {
+        Some(tls) => {
+            log::info!("Sirviendo HTTPS en {}:{}", direccion.0, direccion.1);
+            servidor.bind_rustls_0_23(direccion, tls)?
+        }
+        None => {
+            log::info!("Sirviendo HTTP en {}:{}", direccion.0, direccion.1);
+            servidor.bind(direccion)?
+        }
+    };
+
+    let servidor = servidor.run();
+    let manejador = servidor.handle();
+    let http = tokio::spawn(servidor);
+
+    let senal = senales.await?;
+    log::info!("{} recibida, finalizando...", senal);
+    println!("\n{} recibida, cerrando el servicio", senal);
+
+    cerrar(app_cierre, monitor, manejador, http, gestor_crontab, usar_crontab).await;
+    Ok(())
+}
+
+/// Los pasos del cierre en orden, cada uno con su tiempo límite
+async fn cerrar(
+    app: web::Data<AppState>,
+    monitor: tokio::task::JoinHandle<()>,
+    manejador: actix_web::dev::ServerHandle,
+    http: tokio::task::JoinHandle<std::io::Result<()>>,
+    gestor_crontab: Arc<GestorCrontab>,
+    usar_crontab: bool,
+) {
+    // El monitor termina el ciclo en curso, las métricas se escriben dentro del ciclo
+    app.cierre.iniciar();
+    let detener_monitor = monitor.abort_handle();
+    let monitor_detenido = cierre::paso("Detener el monitor", cierre::ESPERA_MONITOR, async {
+        monitor.await.map_err(|e| e.to_string())
+    })
+    .await;
+    if !monitor_detenido {
+        detener_monitor.abort();
+    }
+
+    cierre::paso("Vaciar las conexiones HTTP", cierre::ESPERA_HTTP + Duration::from_secs(1), async {
+        manejador.stop(true).await;
+        http.await.map_err(|e| e.to_string())?.map_err(|e| e.to_string())
+    })
+    .await;
+
+    // Con el HTTP cerrado ya nadie puede volver a programar trabajos o iniciar escenarios
+    app.programador.detener();
+    if let Ok((ejecucion, contenedores)) = app.escenarios.detener() {
+        log::info!("Escenario '{}' detenido por el cierre", ejecucion.escenario);
+        cierre::paso("Quitar los contenedores del escenario", cierre::ESPERA_DOCKER, async {
+            let mut errores = Vec::new();
+            for id in &contenedores {
+                let opciones = RemoveContainerOptions { force: true, ..Default::default() };
+                if let Err(e) = app.docker.remove_container(id, Some(opciones)).await {
+                    errores.push(format!("{}: {}", id, e));
+                }
+            }
+            if errores.is_empty() { Ok(()) } else { Err(errores.join(", ")) }
+        })
+        .await;
+    }
+
+    cierre::paso("Escribir los logs de peticiones pendientes", cierre::ESPERA_LOGS, async {
+        app.escritor_logs.cerrar().await.map_err(|e| e.to_string())
+    })
+    .await;
+
+    let sesion = app.sesion.clone();
+    cierre::paso("Reporte de la sesión", cierre::ESPERA_REPORTE, async {
+        web::block(move || imprimir_estado_final(&sesion)).await.map_err(|e| e.to_string())
+    })
+    .await;
+
+    cierre::paso("Gráficas finales", cierre::ESPERA_REPORTE, async {
+        let dir_salida = directorio_graficas();
+        let archivos = web::block(move || generar_graficas(&dir_salida).map_err(|e| e.to_string()))
+        .await
+        .map_err(|e| e.to_string())??;
+        println!("Gráficas generadas: {:?}", archivos);
+        Ok(())
+    })
+    .await;
+
+    if !app.logger_container_id.is_empty() {
+        cierre::paso("Eliminar el contenedor logger", cierre::ESPERA_DOCKER, async {
+            let opciones = RemoveContainerOptions { force: true, ..Default::default() };
+            app.docker.remove_container(&app.logger_container_id, Some(opciones)).await.map_err(|e| e.to_string())
+        })
+        .await;
+    }
+
+    if usar_crontab {
+        cierre::paso("Restaurar el crontab", cierre::ESPERA_CRONTAB, async {
+            match web::block(move || gestor_crontab.desinstalar()).await {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(e) => Err(e.to_string()),
+            }
+        })
+        .await;
+    }
+}
+
+/// Subcomando `monitor`: el mismo ciclo de `serve` en la consola, sin servidor HTTP,
+/// programador ni crontab. Al cerrar imprime y guarda el reporte de la sesión.
+pub async fn monitorear(una_vez: bool) -> Result<(), Box<dyn Error>> {
+    let config = config::actual().clone();
+    // Sin contenedor logger los logs de contenedores no se envían a ningún lado
+    let app_state = web::Data::new(AppState::new(config, String::new())?);
+
+    if una_vez {
+        gestionar_contenedores_por_categoria(&app_state).await.map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
+    let senales = cierre::escuchar_senales()?;
+    let sesion = app_state.sesion.clone();
+    let cierre = app_state.cierre.clone();
+    let mut monitor = tokio::spawn(gestionar_contenedores(app_state));
+
+    // El monitor solo termina solo al acabarse una grabación
+    tokio::select! {
+        _ = &mut monitor => {}
+        senal = senales => {
+            println!("\n{} recibida, cerrando el monitor", senal?);
+            cierre.iniciar();
+            let detener_monitor = monitor.abort_handle();
+            if !cierre::paso("Detener el monitor", cierre::ESPERA_MONITOR, async { monitor.await.map_err(|e| e.to_string()) }).await {
+                detener_monitor.abort();
+            }
+        }
+    }
+
+    imprimir_estado_final(&sesion);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contenedor(id: &str, created: i64) -> (String, String, i64) {
+        (id.to_string(), format!("/stress_cpu_{}", id), created)
+    }
+
+    fn ids(containers: &[(String, String, i64)]) -> Vec<&str> {
+        containers.iter().map(|(id, _, _)| id.as_str()).collect()
+    }
+
+    #[test]
+    fn se_conserva_el_mas_nuevo() {
+        let (conservados, sobrantes) =
+            separar_sobrantes(vec![contenedor("a", 1), contenedor("c", 3), contenedor("b", 2)], |_| false);
+        assert_eq!(ids(&conservados), vec!["c"]);
+        assert_eq!(ids(&sobrantes), vec!["b", "a"]);
+    }
+
+    #[test]
+    fn los_protegidos_no_cuentan_como_el_mas_nuevo() {
+        let containers = vec![contenedor("a", 1), contenedor("b", 2), contenedor("protegido", 3)];
+        let (conservados, sobrantes) = separar_sobrantes(containers, |id| id == "protegido");
+        assert_eq!(ids(&conservados), vec!["protegido", "b"]);
+        assert_eq!(ids(&sobrantes), vec!["a"]);
+
+        let (conservados, sobrantes) = separar_sobrantes(vec![contenedor("protegido", 3)], |id| id == "protegido");
+        assert_eq!(ids(&conservados), vec!["protegido"]);
+        assert!(sobrantes.is_empty());
+    }
+}
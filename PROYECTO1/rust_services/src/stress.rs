@@ -0,0 +1,232 @@
+use bollard::container::{Config, CreateContainerOptions, StartContainerOptions};
+use bollard::image::CreateImageOptions;
+use bollard::models::HostConfig;
+use bollard::Docker;
+use chrono::Utc;
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{config, ContainerLog};
+
+pub const CATEGORIAS: [&str; 4] = ["ram", "cpu", "io", "disk"];
+
+// Límites para lanzar stress desde la API o el programador, para no saturar la máquina
+pub const MAX_CANTIDAD: u32 = 10;
+pub const MAX_INTENSIDAD: u32 = 8;
+pub const MAX_DURACION: u32 = 600;
+
+// Margen sobre la duración del stress, el contenedor tarda un poco en arrancar y en borrarse
+const MARGEN_PROTECCION: Duration = Duration::from_secs(30);
+
+/// Contenedores lanzados por `POST /api/stress` que el reconciliador no debe quitar
+/// mientras dure su stress, si no de varios de la misma categoría solo quedaría uno
+#[derive(Debug, Default)]
+pub struct Protegidos {
+    hasta: Mutex<HashMap<String, Instant>>,
+}
+
+impl Protegidos {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn proteger(&self, id: &str, duracion_segundos: u32) {
+        let hasta = Instant::now() + Duration::from_secs(duracion_segundos.into()) + MARGEN_PROTECCION;
+        self.hasta.lock().unwrap().insert(id.to_string(), hasta);
+    }
+
+    pub fn contiene(&self, id: &str) -> bool {
+        let mut hasta = self.hasta.lock().unwrap();
+        let ahora = Instant::now();
+        hasta.retain(|_, fin| *fin > ahora);
+        hasta.contains_key(id)
+    }
+}
+
+/// Cómo se estresa una categoría: `workers` por cada punto de intensidad y,
+/// en `ram` y `disk`, los bytes de cada worker (`64M`, `1G`, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresetStress {
+    pub workers: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<String>,
+}
+
+/// Sección `[stress]` de la configuración. Los valores por defecto son los de `script.sh`
+/// (`--vm 1 --vm-bytes 64M`, `--cpu 2`, `--io 1`, `--hdd 1 --hdd-bytes 100M`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigStress {
+    pub imagen: String,
+    pub ram: PresetStress,
+    pub cpu: PresetStress,
+    pub io: PresetStress,
+    pub disk: PresetStress,
+}
+
+impl Default for ConfigStress {
+    fn default() -> Self {
+        ConfigStress {
+            imagen: "containerstack/alpine-stress".to_string(),
+            ram: PresetStress { workers: 1, bytes: Some("64M".to_string()) },
+            cpu: PresetStress { workers: 2, bytes: None },
+            io: PresetStress { workers: 1, bytes: None },
+            disk: PresetStress { workers: 1, bytes: Some("100M".to_string()) },
+        }
+    }
+}
+
+impl ConfigStress {
+    pub fn validar(&self) -> Result<(), String> {
+        if self.imagen.trim().is_empty() {
+            return Err("stress.imagen no puede estar vacía".to_string());
+        }
+        for (categoria, preset, usa_bytes) in
+            [("ram", &self.ram, true), ("cpu", &self.cpu, false), ("io", &self.io, false), ("disk", &self.disk, true)]
+        {
+            if preset.workers == 0 || preset.workers > 64 / MAX_INTENSIDAD {
+                return Err(format!("stress.{}.workers debe estar entre 1 y {}", categoria, 64 / MAX_INTENSIDAD));
+            }
+            match (&preset.bytes, usa_bytes) {
+                (Some(bytes), true) if !bytes_validos(bytes) => {
+                    return Err(format!("stress.{}.bytes '{}' no es un tamaño de stress como 64M o 1G", categoria, bytes))
+                }
+                (None, true) => return Err(format!("stress.{}.bytes es obligatorio", categoria)),
+                (Some(_), false) => return Err(format!("stress.{} no usa bytes", categoria)),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn bytes_validos(bytes: &str) -> bool {
+    let numero = bytes.strip_suffix(['B', 'K', 'M', 'G', 'b', 'k', 'm', 'g']).unwrap_or(bytes);
+    !numero.is_empty() && numero.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Argumentos de `stress` para cada categoría según los presets de `[stress]`.
+/// La intensidad multiplica la cantidad de workers.
+pub fn argumentos_stress(categoria: &str, intensidad: u32, duracion_segundos: u32) -> Option<Vec<String>> {
+    let presets = &config::actual().stress;
+    let (preset, flag, flag_bytes) = match categoria {
+        "ram" => (&presets.ram, "--vm", "--vm-bytes"),
+        "cpu" => (&presets.cpu, "--cpu", ""),
+        "io" => (&presets.io, "--io", ""),
+        "disk" => (&presets.disk, "--hdd", "--hdd-bytes"),
+        _ => return None,
+    };
+
+    let mut args = vec![flag.to_string(), (preset.workers * intensidad).to_string()];
+    if let Some(bytes) = &preset.bytes {
+        args.push(flag_bytes.to_string());
+        args.push(bytes.clone());
+    }
+    args.push("-t".into());
+    args.push(format!("{}s", duracion_segundos));
+    Some(args)
+}
+
+/// Crea e inicia un contenedor de stress, descarga la imagen si no está.
+/// Devuelve el ID y el nombre del contenedor.
+pub async fn lanzar_contenedor(docker: &Docker, categoria: &str, args: &[String]) -> Result<(String, String), bollard::errors::Error> {
+    // Mismo formato de nombre que script.sh para que el reconciliador lo clasifique
+    let ahora = Utc::now();
+    let container_name = format!("stress_{}_{}_{:06}", categoria, ahora.timestamp(), ahora.timestamp_subsec_micros());
+
+    let mut cmd = vec!["stress".to_string()];
+    cmd.extend_from_slice(args);
+
+    let imagen = config::actual().stress.imagen.as_str();
+    let config = Config {
+        image: Some(imagen.to_string()),
+        cmd: Some(cmd),
+        host_config: Some(HostConfig {
+            auto_remove: Some(true),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let options = CreateContainerOptions { name: container_name.clone(), platform: None };
+
+    let creado = match docker.create_container(Some(options.clone()), config.clone()).await {
+        Err(bollard::errors::Error::DockerResponseServerError { status_code: 404, .. }) => {
+            log::info!("Descargando la imagen {}", imagen);
+            docker
+                .create_image(Some(CreateImageOptions { from_image: imagen, tag: "latest", ..Default::default() }), None, None)
+                .try_collect::<Vec<_>>()
+                .await?;
+            docker.create_container(Some(options), config).await?
+        }
+        resultado => resultado?,
+    };
+
+    docker.start_container(&creado.id, None::<StartContainerOptions<String>>).await?;
+    log::info!("Contenedor {} iniciado con: stress {}", container_name, args.join(" "));
+
+    Ok((creado.id, container_name))
+}
+
+/// Lo mismo que `script.sh`: crea `cantidad` contenedores de categorías al azar (vacío es cualquiera
+/// de `CATEGORIAS`). Si Docker falla se detiene y devuelve también los que sí se crearon.
+pub async fn generar_carga(
+    docker: &Docker,
+    cantidad: u32,
+    duracion: u32,
+    intensidad: u32,
+    categorias: &[String],
+) -> (Vec<ContainerLog>, Option<String>) {
+    let categorias: Vec<String> = if categorias.is_empty() {
+        CATEGORIAS.iter().map(|c| c.to_string()).collect()
+    } else {
+        categorias.to_vec()
+    };
+
+    let mut logs = Vec::new();
+    for _ in 0..cantidad {
+        let categoria = &categorias[(aleatorio() % categorias.len() as u64) as usize];
+        let args = match argumentos_stress(categoria, intensidad, duracion) {
+            Some(args) => args,
+            None => return (logs, Some(format!("Categoría '{}' no válida", categoria))),
+        };
+
+        match lanzar_contenedor(docker, categoria, &args).await {
+            Ok((_, name)) => logs.push(ContainerLog {
+                timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+                category: categoria.clone(),
+                name,
+                action: "creado".to_string(),
+            }),
+            Err(e) => return (logs, Some(e.to_string())),
+        }
+    }
+    (logs, None)
+}
+
+// Número al azar sin agregar otra dependencia, RandomState usa llaves aleatorias en cada instancia
+fn aleatorio() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos());
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workers_fuera_de_rango_no_desbordan() {
+        assert!(ConfigStress::default().validar().is_ok());
+        for workers in [0, 64 / MAX_INTENSIDAD + 1, 600_000_000, u32::MAX] {
+            let mut config = ConfigStress::default();
+            config.cpu.workers = workers;
+            let error = config.validar().unwrap_err();
+            assert!(error.starts_with("stress.cpu.workers"), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+
+/// Sección `[tls]` de la configuración. Con `cert` y `key` el servidor atiende HTTPS,
+/// y con `ca_clientes` además pide certificado de cliente firmado por esa CA (mTLS).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigTls {
+    pub cert: Option<String>,
+    pub key: Option<String>,
+    pub ca_clientes: Option<String>,
+}
+
+/// Devuelve `None` si no hay TLS configurado
+pub fn configurar(config: &ConfigTls) -> Result<Option<ServerConfig>, Box<dyn Error>> {
+    let (cert_path, key_path) = match (&config.cert, &config.key) {
+        (Some(cert), Some(key)) => (cert, key),
+        (None, None) => {
+            if config.ca_clientes.is_some() {
+                return Err("tls.ca_clientes necesita tls.cert y tls.key".into());
+            }
+            return Ok(None);
+        }
+        _ => return Err("tls.cert y tls.key van juntos".into()),
+    };
+
+    let certs = leer_certificados(cert_path)?;
+    let key = leer_llave(key_path)?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone()).with_safe_default_protocol_versions()?;
+
+    let builder = match &config.ca_clientes {
+        Some(ca_path) => {
+            let mut raices = RootCertStore::empty();
+            for cert in leer_certificados(ca_path)? {
+                raices.add(cert).map_err(|e| format!("Certificado inválido en {}: {}", ca_path, e))?;
+            }
+            let verificador = WebPkiClientVerifier::builder_with_provider(Arc::new(raices), provider).build()?;
+            builder.with_client_cert_verifier(verificador)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server_config = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("No se pudo usar el certificado {}: {}", cert_path, e))?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(Some(server_config))
+}
+
+fn leer_certificados(file_path: &str) -> Result<Vec<CertificateDer<'static>>, Box<dyn Error>> {
+    let file = File::open(file_path).map_err(|e| format!("No se pudo abrir {}: {}", file_path, e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file)).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(format!("{} no tiene certificados PEM", file_path).into());
+    }
+    Ok(certs)
+}
+
+fn leer_llave(file_path: &str) -> Result<PrivateKeyDer<'static>, Box<dyn Error>> {
+    let file = File::open(file_path).map_err(|e| format!("No se pudo abrir {}: {}", file_path, e))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))?
+        .ok_or_else(|| format!("{} no tiene una llave privada PEM", file_path).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    /// Escribe un certificado autofirmado y su llave en `dir`
+    fn autofirmado(dir: &Path) -> (String, String) {
+        let generado = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert = dir.join("cert.pem");
+        let key = dir.join("key.pem");
+        fs::write(&cert, generado.cert.pem()).unwrap();
+        fs::write(&key, generado.key_pair.serialize_pem()).unwrap();
+        (cert.display().to_string(), key.display().to_string())
+    }
+
+    #[test]
+    fn sin_tls_devuelve_none() {
+        assert!(configurar(&ConfigTls::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn cert_y_llave_arman_la_configuracion() {
+        let dir = tempfile::tempdir().unwrap();
+        let (cert, key) = autofirmado(dir.path());
+        let config = ConfigTls { cert: Some(cert.clone()), key: Some(key), ca_clientes: None };
+        let server = configurar(&config).unwrap().unwrap();
+        assert_eq!(server.alpn_protocols, vec![b"h2".to_vec(), b"http/1.1".to_vec()]);
+
+        // La propia CA autofirmada sirve como CA de clientes
+        let config = ConfigTls { ca_clientes: Some(cert), ..config };
+        assert!(configurar(&config).unwrap().is_some());
+    }
+
+    #[test]
+    fn cert_sin_llave_falla() {
+        let dir = tempfile::tempdir().unwrap();
+        let (cert, _) = autofirmado(dir.path());
+        let error = configurar(&ConfigTls { cert: Some(cert), key: None, ca_clientes: None }).unwrap_err();
+        assert!(error.to_string().contains("van juntos"), "{}", error);
+    }
+
+    #[test]
+    fn ca_clientes_invalida_falla() {
+        let dir = tempfile::tempdir().unwrap();
+        let (cert, key) = autofirmado(dir.path());
+        let sin_pem = dir.path().join("ca.pem");
+        fs::write(&sin_pem, "esto no es un certificado\n").unwrap();
+
+        let config = ConfigTls { cert: Some(cert.clone()), key: Some(key.clone()), ca_clientes: Some(sin_pem.display().to_string()) };
+        let error = configurar(&config).unwrap_err();
+        assert!(error.to_string().contains("no tiene certificados PEM"), "{}", error);
+
+        let inexistente = dir.path().join("no-existe.pem").display().to_string();
+        let config = ConfigTls { cert: Some(cert), key: Some(key), ca_clientes: Some(inexistente) };
+        assert!(configurar(&config).unwrap_err().to_string().contains("No se pudo abrir"));
+    }
+}
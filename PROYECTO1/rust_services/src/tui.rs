@@ -0,0 +1,527 @@
+//! `top`: vista de pantalla completa al estilo de `top` con lo que reporta el módulo de kernel,
+//! la categoría de cada proceso, el contenedor de Docker al que pertenece y los eventos de los contenedores.
+
+use bollard::container::{KillContainerOptions, ListContainersOptions, TopOptions};
+use bollard::Docker;
+use crossterm::event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers};
+use futures::StreamExt;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Gauge, List, ListItem, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+
+use crate::dashboard::ContenedorVivo;
+use crate::{clasificar_contenedor, enriquecer_contenedores, enviar_container_logs, leer_sysinfo, ContainerLog, SystemStats};
+
+const MAX_EVENTOS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Orden {
+    Pid,
+    Nombre,
+    Categoria,
+    Cpu,
+    Memoria,
+    Rss,
+    Disco,
+    Io,
+}
+
+impl Orden {
+    fn siguiente(self) -> Orden {
+        match self {
+            Orden::Pid => Orden::Nombre,
+            Orden::Nombre => Orden::Categoria,
+            Orden::Categoria => Orden::Cpu,
+            Orden::Cpu => Orden::Memoria,
+            Orden::Memoria => Orden::Rss,
+            Orden::Rss => Orden::Disco,
+            Orden::Disco => Orden::Io,
+            Orden::Io => Orden::Pid,
+        }
+    }
+
+    /// Columna de la tabla que se marca como ordenada
+    fn columna(self) -> usize {
+        match self {
+            Orden::Pid => 0,
+            Orden::Nombre => 1,
+            Orden::Categoria => 2,
+            Orden::Cpu => 4,
+            Orden::Memoria => 5,
+            Orden::Rss => 6,
+            Orden::Disco => 8,
+            Orden::Io => 9,
+        }
+    }
+}
+
+const COLUMNAS: [&str; 11] = ["PID", "Nombre", "Cat.", "Contenedor", "CPU %", "MEM %", "RSS KB", "VSZ KB", "Disco KB", "IO lect.", "IO escr."];
+
+/// Contenedor de Docker dueño de un proceso
+#[derive(Debug, Clone)]
+struct Dueno {
+    id: String,
+    nombre: String,
+}
+
+struct Fila {
+    proceso: ContenedorVivo,
+    dueno: Option<Dueno>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Accion {
+    Detener,
+    Matar,
+}
+
+impl Accion {
+    fn verbo(self) -> &'static str {
+        match self {
+            Accion::Detener => "detener",
+            Accion::Matar => "matar",
+        }
+    }
+}
+
+/// Lo que responde Docker a una acción que se corrió en segundo plano
+struct ResultadoAccion {
+    id: String,
+    log: Result<ContainerLog, String>,
+}
+
+struct Top {
+    system: Option<SystemStats>,
+    filas: Vec<Fila>,
+    tabla: TableState,
+    /// PID seleccionado, se conserva aunque las filas cambien de lugar
+    seleccionado: Option<u32>,
+    orden: Orden,
+    descendente: bool,
+    eventos: VecDeque<ContainerLog>,
+    /// Contenedores de la vuelta anterior, `None` hasta la primera
+    conocidos: Option<HashMap<String, String>>,
+    /// Detenidos o matados desde aquí, así no se anuncian dos veces
+    finalizados: HashSet<String>,
+    pendiente: Option<(Accion, Dueno)>,
+    mensaje: Option<String>,
+    error_sysinfo: Option<String>,
+    error_docker: Option<String>,
+}
+
+/// Corre la interfaz hasta que se presione `q`, refrescando cada `intervalo` segundos
+pub async fn ejecutar(intervalo: u64) -> Result<(), Box<dyn Error>> {
+    let docker = Arc::new(Docker::connect_with_local_defaults()?);
+    // `init` también instala un panic hook que devuelve la terminal a su estado normal
+    let mut terminal = ratatui::init();
+    let resultado = ciclo(&mut terminal, docker, intervalo).await;
+    ratatui::restore();
+    resultado
+}
+
+async fn ciclo(terminal: &mut DefaultTerminal, docker: Arc<Docker>, intervalo: u64) -> Result<(), Box<dyn Error>> {
+    let mut top = Top::new();
+    let mut teclas = EventStream::new();
+    let mut refresco = tokio::time::interval(Duration::from_secs(intervalo.max(1)));
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    loop {
+        terminal.draw(|frame| top.dibujar(frame))?;
+
+        tokio::select! {
+            _ = refresco.tick() => top.refrescar(&docker).await,
+            Some(resultado) = rx.recv() => top.terminar_accion(resultado),
+            evento = teclas.next() => match evento {
+                Some(Ok(Event::Key(tecla))) if tecla.kind == KeyEventKind::Press => {
+                    let salir = tecla.code == KeyCode::Char('q')
+                        || tecla.code == KeyCode::Esc && top.pendiente.is_none()
+                        || tecla.code == KeyCode::Char('c') && tecla.modifiers.contains(KeyModifiers::CONTROL);
+                    if salir {
+                        return Ok(());
+                    }
+                    top.tecla(tecla.code, &docker, &tx);
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+                None => return Ok(()),
+            },
+        }
+    }
+}
+
+impl Top {
+    fn new() -> Self {
+        Top {
+            system: None,
+            filas: Vec::new(),
+            tabla: TableState::default(),
+            seleccionado: None,
+            orden: Orden::Cpu,
+            descendente: true,
+            eventos: VecDeque::new(),
+            conocidos: None,
+            finalizados: HashSet::new(),
+            pendiente: None,
+            mensaje: None,
+            error_sysinfo: None,
+            error_docker: None,
+        }
+    }
+
+    async fn refrescar(&mut self, docker: &Docker) {
+        let procesos = match leer_sysinfo() {
+            Ok(sys_info) => {
+                self.error_sysinfo = None;
+                self.system = Some(sys_info.system);
+                enriquecer_contenedores(sys_info.containers)
+            }
+            Err(e) => {
+                self.error_sysinfo = Some(format!("No se pudo leer la información del sistema: {}", e));
+                Vec::new()
+            }
+        };
+
+        let duenos = match contenedores_docker(docker).await {
+            Ok((activos, duenos)) => {
+                self.error_docker = None;
+                self.registrar_cambios(activos);
+                duenos
+            }
+            Err(e) => {
+                self.error_docker = Some(format!("Error al comunicarse con Docker: {}", e));
+                HashMap::new()
+            }
+        };
+
+        self.filas = procesos
+            .into_iter()
+            .map(|proceso| {
+                let dueno = duenos.get(&proceso.info.pid).cloned();
+                Fila { proceso, dueno }
+            })
+            .collect();
+        self.ordenar();
+    }
+
+    /// Compara con la vuelta anterior y anuncia los contenedores de stress que aparecieron o terminaron
+    fn registrar_cambios(&mut self, activos: HashMap<String, String>) {
+        let ahora = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+        if let Some(conocidos) = &self.conocidos {
+            let creados = activos.iter().filter(|(id, _)| !conocidos.contains_key(*id)).map(|(_, nombre)| (nombre, "creado"));
+            let terminados = conocidos
+                .iter()
+                .filter(|(id, _)| !activos.contains_key(*id) && !self.finalizados.contains(*id))
+                .map(|(_, nombre)| (nombre, "terminado"));
+            for (nombre, action) in creados.chain(terminados) {
+                if let Some(category) = clasificar_contenedor(nombre) {
+                    self.eventos.push_front(ContainerLog { timestamp: ahora, category, name: nombre.clone(), action: action.to_string() });
+                }
+            }
+            self.eventos.truncate(MAX_EVENTOS);
+        }
+
+        self.finalizados.retain(|id| activos.contains_key(id));
+        self.conocidos = Some(activos);
+    }
+
+    fn ordenar(&mut self) {
+        match self.orden {
+            Orden::Pid => self.filas.sort_by_key(|f| f.proceso.info.pid),
+            Orden::Nombre => self.filas.sort_by(|a, b| a.proceso.info.name.cmp(&b.proceso.info.name)),
+            Orden::Categoria => self.filas.sort_by(|a, b| a.proceso.categoria.cmp(&b.proceso.categoria)),
+            Orden::Cpu => self.filas.sort_by_key(|f| f.proceso.info.cpu_percent),
+            Orden::Memoria => self.filas.sort_by_key(|f| f.proceso.info.memory_percent),
+            Orden::Rss => self.filas.sort_by_key(|f| f.proceso.info.memory_rss),
+            Orden::Disco => self.filas.sort_by_key(|f| f.proceso.info.disk_usage),
+            Orden::Io => self.filas.sort_by_key(|f| f.proceso.info.io_read_bytes + f.proceso.info.io_write_bytes),
+        }
+        if self.descendente {
+            self.filas.reverse();
+        }
+
+        // La selección sigue al mismo proceso, si ya no está se queda en la misma posición
+        let indice = self
+            .seleccionado
+            .and_then(|pid| self.filas.iter().position(|f| f.proceso.info.pid == pid))
+            .or_else(|| self.tabla.selected().map(|i| i.min(self.filas.len().saturating_sub(1))))
+            .or(Some(0))
+            .filter(|_| !self.filas.is_empty());
+        self.seleccionar(indice);
+    }
+
+    fn seleccionar(&mut self, indice: Option<usize>) {
+        self.tabla.select(indice);
+        self.seleccionado = indice.map(|i| self.filas[i].proceso.info.pid);
+    }
+
+    fn mover(&mut self, paso: isize) {
+        if self.filas.is_empty() {
+            return;
+        }
+        let actual = self.tabla.selected().unwrap_or(0) as isize;
+        let indice = (actual + paso).clamp(0, self.filas.len() as isize - 1) as usize;
+        self.seleccionar(Some(indice));
+    }
+
+    fn tecla(&mut self, codigo: KeyCode, docker: &Arc<Docker>, tx: &mpsc::UnboundedSender<ResultadoAccion>) {
+        // Con una acción pendiente solo se espera la confirmación
+        if let Some((accion, dueno)) = self.pendiente.take() {
+            self.mensaje = match codigo {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    lanzar_accion(docker.clone(), accion, dueno.clone(), tx.clone());
+                    Some(format!("Intentando {} {}...", accion.verbo(), dueno.nombre))
+                }
+                _ => Some("Cancelado".to_string()),
+            };
+            return;
+        }
+
+        self.mensaje = None;
+        match codigo {
+            KeyCode::Up => self.mover(-1),
+            KeyCode::Down => self.mover(1),
+            KeyCode::PageUp => self.mover(-10),
+            KeyCode::PageDown => self.mover(10),
+            KeyCode::Home => self.mover(isize::MIN / 2),
+            KeyCode::End => self.mover(isize::MAX / 2),
+            KeyCode::Char('o') => {
+                self.orden = self.orden.siguiente();
+                self.ordenar();
+            }
+            KeyCode::Char('i') => {
+                self.descendente = !self.descendente;
+                self.ordenar();
+            }
+            KeyCode::Char('s') => self.pedir_accion(Accion::Detener),
+            KeyCode::Char('k') => self.pedir_accion(Accion::Matar),
+            _ => {}
+        }
+    }
+
+    fn pedir_accion(&mut self, accion: Accion) {
+        let fila = match self.tabla.selected().and_then(|i| self.filas.get(i)) {
+            Some(fila) => fila,
+            None => return,
+        };
+        self.mensaje = match &fila.dueno {
+            None => Some(format!("No se encontró el contenedor del proceso {}", fila.proceso.info.pid)),
+            // Igual que en la API solo se controlan contenedores de stress
+            Some(dueno) if clasificar_contenedor(&dueno.nombre).is_none() => {
+                Some(format!("'{}' no es un contenedor de stress", dueno.nombre))
+            }
+            Some(dueno) => {
+                self.pendiente = Some((accion, dueno.clone()));
+                Some(format!("¿{} {}? (y/n)", capitalizar(accion.verbo()), dueno.nombre))
+            }
+        };
+    }
+
+    fn terminar_accion(&mut self, resultado: ResultadoAccion) {
+        self.mensaje = match resultado.log {
+            Ok(log) => {
+                self.finalizados.insert(resultado.id);
+                let mensaje = format!("Contenedor {} {}", log.name, log.action);
+                self.eventos.push_front(log);
+                self.eventos.truncate(MAX_EVENTOS);
+                Some(mensaje)
+            }
+            Err(e) => Some(e),
+        };
+    }
+
+    fn dibujar(&mut self, frame: &mut Frame) {
+        let [medidores, tabla, eventos, pie] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(6), Constraint::Length(9), Constraint::Length(1)]).areas(frame.area());
+        let [cpu, ram] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(medidores);
+
+        let (ratio_cpu, etiqueta_cpu, ratio_ram, etiqueta_ram) = match &self.system {
+            Some(system) => {
+                let ratio_ram = if system.ram_total > 0 { system.ram_ocupada as f64 / system.ram_total as f64 } else { 0.0 };
+                (
+                    f64::from(system.cpu_usada.min(100)) / 100.0,
+                    format!("{}%", system.cpu_usada),
+                    ratio_ram.clamp(0.0, 1.0),
+                    format!("{} / {} MB ({:.0}%)", system.ram_ocupada / 1024, system.ram_total / 1024, ratio_ram * 100.0),
+                )
+            }
+            None => (0.0, "-".to_string(), 0.0, "-".to_string()),
+        };
+        frame.render_widget(
+            Gauge::default().block(Block::bordered().title(" CPU ")).gauge_style(color_uso(ratio_cpu)).ratio(ratio_cpu).label(etiqueta_cpu),
+            cpu,
+        );
+        frame.render_widget(
+            Gauge::default().block(Block::bordered().title(" RAM ")).gauge_style(color_uso(ratio_ram)).ratio(ratio_ram).label(etiqueta_ram),
+            ram,
+        );
+
+        let flecha = if self.descendente { "▼" } else { "▲" };
+        let encabezado = Row::new(COLUMNAS.iter().enumerate().map(|(i, titulo)| {
+            if i == self.orden.columna() {
+                Cell::from(format!("{}{}", titulo, flecha)).style(Style::new().fg(Color::Yellow))
+            } else {
+                Cell::from(*titulo)
+            }
+        }))
+        .style(Style::new().add_modifier(Modifier::BOLD));
+
+        let filas = self.filas.iter().map(|fila| {
+            let info = &fila.proceso.info;
+            let categoria = fila.proceso.categoria.as_deref().unwrap_or("-");
+            Row::new(vec![
+                Cell::from(info.pid.to_string()),
+                Cell::from(info.name.clone()),
+                Cell::from(categoria.to_string()).style(Style::new().fg(color_categoria(categoria))),
+                Cell::from(fila.dueno.as_ref().map_or("-".to_string(), |d| d.nombre.clone())),
+                Cell::from(info.cpu_percent.to_string()),
+                Cell::from(info.memory_percent.to_string()),
+                Cell::from(info.memory_rss.to_string()),
+                Cell::from(info.virtual_memory.to_string()),
+                Cell::from(info.disk_usage.to_string()),
+                Cell::from(info.io_read_bytes.to_string()),
+                Cell::from(info.io_write_bytes.to_string()),
+            ])
+        });
+
+        let anchos = [
+            Constraint::Length(7),
+            Constraint::Min(10),
+            Constraint::Length(6),
+            Constraint::Min(14),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+        ];
+        let titulo_tabla = format!(" Procesos ({}) ", self.filas.len());
+        let tabla_widget = Table::new(filas, anchos)
+            .header(encabezado)
+            .block(Block::bordered().title(titulo_tabla))
+            .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(tabla_widget, tabla, &mut self.tabla);
+
+        let lineas: Vec<ListItem> = self
+            .eventos
+            .iter()
+            .map(|log| {
+                let hora = chrono::DateTime::from_timestamp(log.timestamp as i64, 0)
+                    .map(|fecha| fecha.with_timezone(&chrono::Local).format("%H:%M:%S").to_string())
+                    .unwrap_or_default();
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{} ", hora), Style::new().fg(Color::DarkGray)),
+                    Span::styled(format!("{:<5} ", log.category), Style::new().fg(color_categoria(&log.category))),
+                    Span::raw(format!("{} {}", log.name, log.action)),
+                ]))
+            })
+            .collect();
+        frame.render_widget(List::new(lineas).block(Block::bordered().title(" Eventos ")), eventos);
+
+        // Lo último que hizo el usuario se ve antes que los errores, que siguen ahí en la próxima tecla
+        let pie_widget = match (&self.mensaje, self.error_sysinfo.as_ref().or(self.error_docker.as_ref())) {
+            (Some(mensaje), _) if self.pendiente.is_some() => Paragraph::new(mensaje.as_str()).style(Style::new().fg(Color::Yellow)),
+            (Some(mensaje), _) => Paragraph::new(mensaje.as_str()),
+            (None, Some(error)) => Paragraph::new(error.as_str()).style(Style::new().fg(Color::Red)),
+            (None, None) => Paragraph::new("q salir  ↑↓ mover  o ordenar  i invertir  s detener  k matar").style(Style::new().fg(Color::DarkGray)),
+        };
+        frame.render_widget(pie_widget, pie);
+    }
+}
+
+/// Contenedores que están corriendo (id → nombre) y el dueño de cada PID del host
+async fn contenedores_docker(docker: &Docker) -> Result<(HashMap<String, String>, HashMap<u32, Dueno>), bollard::errors::Error> {
+    let containers = docker.list_containers(Some(ListContainersOptions::<String>::default())).await?;
+
+    let mut activos = HashMap::new();
+    let mut duenos = HashMap::new();
+    for container in containers {
+        let (id, nombre) = match (container.id, container.names.and_then(|n| n.into_iter().next())) {
+            (Some(id), Some(nombre)) => (id, nombre.trim_start_matches('/').to_string()),
+            _ => continue,
+        };
+
+        // `docker top` da los PID del host de todos los procesos, también los hijos que crea stress
+        if let Ok(top) = docker.top_processes(&id, None::<TopOptions<String>>).await {
+            let columna_pid = top.titles.unwrap_or_default().iter().position(|t| t == "PID");
+            if let Some(columna) = columna_pid {
+                for proceso in top.processes.unwrap_or_default() {
+                    if let Some(pid) = proceso.get(columna).and_then(|p| p.parse::<u32>().ok()) {
+                        duenos.insert(pid, Dueno { id: id.clone(), nombre: nombre.clone() });
+                    }
+                }
+            }
+        }
+        activos.insert(id, nombre);
+    }
+    Ok((activos, duenos))
+}
+
+/// Corre la acción en otra tarea, `docker stop` puede tardar hasta 10 s y la interfaz no debe congelarse
+fn lanzar_accion(docker: Arc<Docker>, accion: Accion, dueno: Dueno, tx: mpsc::UnboundedSender<ResultadoAccion>) {
+    tokio::spawn(async move {
+        let resultado = match accion {
+            Accion::Detener => docker.stop_container(&dueno.id, None).await.map(|_| "detenido"),
+            Accion::Matar => docker
+                .kill_container(&dueno.id, Some(KillContainerOptions { signal: "SIGKILL" }))
+                .await
+                .map(|_| "matado"),
+        };
+
+        let log = match resultado {
+            Ok(action) => {
+                let container_log = ContainerLog {
+                    timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+                    category: clasificar_contenedor(&dueno.nombre).unwrap_or_default(),
+                    name: dueno.nombre.clone(),
+                    action: action.to_string(),
+                };
+                let logs = vec![container_log.clone()];
+                if let Ok(Err(e)) = tokio::task::spawn_blocking(move || enviar_container_logs(&logs)).await {
+                    log::error!("No se pudo escribir el log del contenedor: {}", e);
+                }
+                Ok(container_log)
+            }
+            Err(e) => Err(format!("No se pudo {} {}: {}", accion.verbo(), dueno.nombre, e)),
+        };
+        let _ = tx.send(ResultadoAccion { id: dueno.id, log });
+    });
+}
+
+fn color_uso(ratio: f64) -> Style {
+    let color = if ratio >= 0.9 {
+        Color::Red
+    } else if ratio >= 0.7 {
+        Color::Yellow
+    } else {
+        Color::Green
+    };
+    Style::new().fg(color)
+}
+
+fn color_categoria(categoria: &str) -> Color {
+    match categoria {
+        "cpu" => Color::Red,
+        "ram" => Color::Magenta,
+        "io" => Color::Cyan,
+        "disk" => Color::Blue,
+        _ => Color::Gray,
+    }
+}
+
+fn capitalizar(texto: &str) -> String {
+    let mut letras = texto.chars();
+    match letras.next() {
+        Some(primera) => primera.to_uppercase().chain(letras).collect(),
+        None => String::new(),
+    }
+}
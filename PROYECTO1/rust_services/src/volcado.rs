@@ -0,0 +1,371 @@
+//! `dump`: lo que reporta el módulo de kernel en tabla, JSON, YAML o CSV,
+//! y con `--watch` los cambios entre una muestra y otra para poder usarlo desde scripts.
+
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::dashboard::ContenedorVivo;
+use crate::{enriquecer_contenedores, leer_sysinfo, SystemStats};
+
+/// Campos que se pueden pedir con `--campos`, en el orden en que salen por defecto
+pub const CAMPOS: [&str; 11] = [
+    "pid",
+    "name",
+    "categoria",
+    "cpu_percent",
+    "memory_percent",
+    "memory_rss",
+    "virtual_memory",
+    "disk_usage",
+    "io_read_bytes",
+    "io_write_bytes",
+    "cmdline",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Formato {
+    Table,
+    Json,
+    Yaml,
+    Csv,
+}
+
+/// Cuánto tiene que cambiar un proceso entre muestras para reportarlo en `--watch`
+#[derive(Debug, Clone, Copy)]
+pub struct Umbrales {
+    /// Puntos de porcentaje de CPU
+    pub cpu: u8,
+    /// Porcentaje de cambio del RSS respecto a la muestra anterior
+    pub rss: u64,
+}
+
+/// Un proceso con solo los campos pedidos, en el mismo orden
+struct Fila(Vec<(&'static str, Value)>);
+
+impl Serialize for Fila {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut mapa = serializer.serialize_map(Some(self.0.len()))?;
+        for (campo, valor) in &self.0 {
+            mapa.serialize_entry(campo, valor)?;
+        }
+        mapa.end()
+    }
+}
+
+#[derive(Serialize)]
+struct Instantanea {
+    timestamp: u64,
+    system: SystemStats,
+    containers: Vec<Fila>,
+}
+
+/// Lo que cambió de un proceso entre dos muestras de `--watch`
+#[derive(Debug, Serialize)]
+struct Cambio {
+    timestamp: u64,
+    /// `nuevo`, `terminado`, `cpu` o `rss`
+    cambio: &'static str,
+    pid: u32,
+    name: String,
+    categoria: Option<String>,
+    antes: Option<u64>,
+    despues: Option<u64>,
+}
+
+/// Revisa `--campos`, sin ninguno se usan todos
+pub fn campos(pedidos: &[String]) -> Result<Vec<&'static str>, String> {
+    if pedidos.is_empty() {
+        return Ok(CAMPOS.to_vec());
+    }
+    pedidos
+        .iter()
+        .map(|pedido| {
+            CAMPOS
+                .iter()
+                .find(|campo| *campo == pedido)
+                .copied()
+                .ok_or_else(|| format!("Campo desconocido '{}', use uno de {:?}", pedido, CAMPOS))
+        })
+        .collect()
+}
+
+/// Imprime una sola muestra
+pub fn imprimir(formato: Formato, campos: &[&'static str]) -> Result<(), Box<dyn Error>> {
+    let sys_info = leer_sysinfo()?;
+    let containers = enriquecer_contenedores(sys_info.containers)
+        .iter()
+        .map(|c| seleccionar(c, campos))
+        .collect::<Result<Vec<_>, _>>()?;
+    let instantanea = Instantanea { timestamp: ahora(), system: sys_info.system, containers };
+
+    let mut salida = io::stdout().lock();
+    match formato {
+        Formato::Json => writeln!(salida, "{}", serde_json::to_string_pretty(&instantanea)?)?,
+        Formato::Yaml => write!(salida, "{}", serde_yaml::to_string(&instantanea)?)?,
+        // CSV solo lleva los procesos, una fila por cada uno
+        Formato::Csv => {
+            let mut csv = csv::Writer::from_writer(salida);
+            csv.write_record(campos)?;
+            for fila in &instantanea.containers {
+                csv.write_record(fila.0.iter().map(|(_, valor)| texto(valor)))?;
+            }
+            csv.flush()?;
+        }
+        Formato::Table => {
+            let system = &instantanea.system;
+            writeln!(
+                salida,
+                "CPU {}%   RAM {} / {} KB   Libre {} KB",
+                system.cpu_usada, system.ram_ocupada, system.ram_total, system.ram_libre
+            )?;
+            writeln!(salida)?;
+            let filas: Vec<Vec<String>> = instantanea
+                .containers
+                .iter()
+                .map(|fila| fila.0.iter().map(|(_, valor)| texto(valor)).collect())
+                .collect();
+            imprimir_tabla(&mut salida, campos, &filas)?;
+        }
+    }
+    Ok(())
+}
+
+/// Toma una muestra cada `intervalo` segundos e imprime lo que cambió respecto a la anterior.
+/// En la primera muestra todos los procesos salen como `nuevo`, así el consumidor parte de cero.
+pub async fn vigilar(formato: Formato, intervalo: u64, umbrales: Umbrales) -> Result<(), Box<dyn Error>> {
+    let mut anteriores: HashMap<u32, ContenedorVivo> = HashMap::new();
+    let mut csv = csv::Writer::from_writer(io::stdout());
+    let mut reloj = tokio::time::interval(Duration::from_secs(intervalo.max(1)));
+
+    loop {
+        reloj.tick().await;
+
+        let sys_info = match leer_sysinfo() {
+            Ok(sys_info) => sys_info,
+            // Si el módulo no responde una vez se sigue intentando
+            Err(e) => {
+                eprintln!("No se pudo leer la información del sistema: {}", e);
+                continue;
+            }
+        };
+        let actuales: HashMap<u32, ContenedorVivo> =
+            enriquecer_contenedores(sys_info.containers).into_iter().map(|c| (c.info.pid, c)).collect();
+
+        let cambios = comparar(&anteriores, &actuales, umbrales);
+        match formato {
+            Formato::Json => {
+                let mut salida = io::stdout().lock();
+                for cambio in &cambios {
+                    writeln!(salida, "{}", serde_json::to_string(cambio)?)?;
+                }
+            }
+            Formato::Yaml => {
+                let mut salida = io::stdout().lock();
+                for cambio in &cambios {
+                    write!(salida, "---\n{}", serde_yaml::to_string(cambio)?)?;
+                }
+            }
+            Formato::Csv => {
+                for cambio in &cambios {
+                    csv.serialize(cambio)?;
+                }
+                csv.flush()?;
+            }
+            Formato::Table => {
+                let mut salida = io::stdout().lock();
+                for cambio in &cambios {
+                    writeln!(salida, "{}", linea_cambio(cambio))?;
+                }
+            }
+        }
+
+        anteriores = actuales;
+    }
+}
+
+fn comparar(anteriores: &HashMap<u32, ContenedorVivo>, actuales: &HashMap<u32, ContenedorVivo>, umbrales: Umbrales) -> Vec<Cambio> {
+    let timestamp = ahora();
+    let cambio = |tipo, c: &ContenedorVivo, antes, despues| Cambio {
+        timestamp,
+        cambio: tipo,
+        pid: c.info.pid,
+        name: c.info.name.clone(),
+        categoria: c.categoria.clone(),
+        antes,
+        despues,
+    };
+
+    let mut cambios = Vec::new();
+    for (pid, actual) in actuales {
+        let anterior = match anteriores.get(pid) {
+            Some(anterior) => anterior,
+            None => {
+                cambios.push(cambio("nuevo", actual, None, None));
+                continue;
+            }
+        };
+
+        if actual.info.cpu_percent.abs_diff(anterior.info.cpu_percent) >= umbrales.cpu {
+            cambios.push(cambio("cpu", actual, Some(anterior.info.cpu_percent.into()), Some(actual.info.cpu_percent.into())));
+        }
+        let (rss_antes, rss_despues) = (anterior.info.memory_rss, actual.info.memory_rss);
+        if rss_antes > 0 && rss_antes.abs_diff(rss_despues) * 100 / rss_antes >= umbrales.rss {
+            cambios.push(cambio("rss", actual, Some(rss_antes), Some(rss_despues)));
+        }
+    }
+    for (pid, anterior) in anteriores {
+        if !actuales.contains_key(pid) {
+            cambios.push(cambio("terminado", anterior, None, None));
+        }
+    }
+
+    cambios.sort_by_key(|c| c.pid);
+    cambios
+}
+
+fn seleccionar(contenedor: &ContenedorVivo, campos: &[&'static str]) -> Result<Fila, serde_json::Error> {
+    let mut valores = match serde_json::to_value(contenedor)? {
+        Value::Object(valores) => valores,
+        _ => serde_json::Map::new(),
+    };
+    Ok(Fila(campos.iter().map(|campo| (*campo, valores.remove(*campo).unwrap_or(Value::Null))).collect()))
+}
+
+/// Valor sin comillas para la tabla y el CSV
+fn texto(valor: &Value) -> String {
+    match valor {
+        Value::String(texto) => texto.clone(),
+        Value::Null => String::new(),
+        valor => valor.to_string(),
+    }
+}
+
+fn imprimir_tabla(salida: &mut impl Write, encabezados: &[&str], filas: &[Vec<String>]) -> io::Result<()> {
+    let anchos: Vec<usize> = encabezados
+        .iter()
+        .enumerate()
+        .map(|(i, encabezado)| filas.iter().map(|fila| fila[i].chars().count()).chain([encabezado.len()]).max().unwrap_or(0))
+        .collect();
+
+    let linea = |celdas: Vec<String>| {
+        celdas
+            .iter()
+            .zip(&anchos)
+            .map(|(celda, ancho)| {
+                // Los números se alinean a la derecha como en `top`
+                if celda.parse::<f64>().is_ok() {
+                    format!("{:>ancho$}", celda)
+                } else {
+                    format!("{:<ancho$}", celda)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    writeln!(salida, "{}", linea(encabezados.iter().map(|e| e.to_uppercase()).collect()))?;
+    for fila in filas {
+        writeln!(salida, "{}", linea(fila.clone()))?;
+    }
+    Ok(())
+}
+
+fn linea_cambio(cambio: &Cambio) -> String {
+    let hora = chrono::DateTime::from_timestamp(cambio.timestamp as i64, 0)
+        .map(|fecha| fecha.with_timezone(&chrono::Local).format("%H:%M:%S").to_string())
+        .unwrap_or_default();
+    let detalle = match (cambio.cambio, cambio.antes, cambio.despues) {
+        ("cpu", Some(antes), Some(despues)) => format!("CPU {}% -> {}%", antes, despues),
+        ("rss", Some(antes), Some(despues)) => format!("RSS {} KB -> {} KB", antes, despues),
+        _ => String::new(),
+    };
+    format!(
+        "{} {:<9} {:>7} {:<20} {:<5} {}",
+        hora,
+        cambio.cambio,
+        cambio.pid,
+        cambio.name,
+        cambio.categoria.as_deref().unwrap_or("-"),
+        detalle
+    )
+    .trim_end()
+    .to_string()
+}
+
+fn ahora() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UMBRALES: Umbrales = Umbrales { cpu: 10, rss: 20 };
+
+    fn proceso(pid: u32, cpu_percent: u8, memory_rss: u64) -> ContenedorVivo {
+        ContenedorVivo {
+            info: crate::ContainerInfo {
+                pid,
+                name: format!("stress_{}", pid),
+                cmdline: String::new(),
+                memory_rss,
+                memory_percent: 1,
+                virtual_memory: 0,
+                cpu_percent,
+                disk_usage: 0,
+                io_read_bytes: 0,
+                io_write_bytes: 0,
+            },
+            categoria: Some("cpu".to_string()),
+        }
+    }
+
+    fn muestra(procesos: Vec<ContenedorVivo>) -> HashMap<u32, ContenedorVivo> {
+        procesos.into_iter().map(|c| (c.info.pid, c)).collect()
+    }
+
+    fn resumen(cambios: &[Cambio]) -> Vec<(&'static str, u32, Option<u64>, Option<u64>)> {
+        cambios.iter().map(|c| (c.cambio, c.pid, c.antes, c.despues)).collect()
+    }
+
+    #[test]
+    fn procesos_nuevos_y_terminados() {
+        let anteriores = muestra(vec![proceso(1, 5, 1000), proceso(2, 5, 1000)]);
+        let actuales = muestra(vec![proceso(2, 5, 1000), proceso(3, 5, 1000)]);
+        let cambios = comparar(&anteriores, &actuales, UMBRALES);
+        assert_eq!(resumen(&cambios), vec![("terminado", 1, None, None), ("nuevo", 3, None, None)]);
+        assert_eq!(cambios[1].categoria.as_deref(), Some("cpu"));
+    }
+
+    #[test]
+    fn cpu_justo_en_el_umbral_se_reporta() {
+        let anteriores = muestra(vec![proceso(1, 50, 1000), proceso(2, 50, 1000), proceso(3, 50, 1000)]);
+        let actuales = muestra(vec![proceso(1, 60, 1000), proceso(2, 41, 1000), proceso(3, 40, 1000)]);
+        let cambios = comparar(&anteriores, &actuales, UMBRALES);
+        assert_eq!(resumen(&cambios), vec![("cpu", 1, Some(50), Some(60)), ("cpu", 3, Some(50), Some(40))]);
+    }
+
+    #[test]
+    fn rss_en_porcentaje_de_la_muestra_anterior() {
+        let anteriores = muestra(vec![proceso(1, 0, 1000), proceso(2, 0, 1000), proceso(3, 0, 0)]);
+        let actuales = muestra(vec![proceso(1, 0, 1200), proceso(2, 0, 801), proceso(3, 0, 5000)]);
+        let cambios = comparar(&anteriores, &actuales, UMBRALES);
+        // Sin RSS antes no hay porcentaje que calcular, no se reporta ni divide por cero
+        assert_eq!(resumen(&cambios), vec![("rss", 1, Some(1000), Some(1200))]);
+    }
+
+    #[test]
+    fn campos_pedidos() {
+        assert_eq!(campos(&[]).unwrap(), CAMPOS.to_vec());
+        assert_eq!(campos(&["name".to_string(), "pid".to_string()]).unwrap(), vec!["name", "pid"]);
+        let error = campos(&["pid".to_string(), "memoria".to_string()]).unwrap_err();
+        assert!(error.starts_with("Campo desconocido 'memoria'"), "{}", error);
+    }
+}
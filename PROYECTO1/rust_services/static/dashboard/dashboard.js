@@ -0,0 +1,135 @@
+// Cantidad de muestras que se ven en las gráficas (10 s por muestra)
+const MAX_PUNTOS = 60;
+
+// Si la API pide token se abre el dashboard con /dashboard/?token=..., se reenvía a las rutas de datos
+const TOKEN = new URLSearchParams(location.search).get("token");
+const consultaToken = TOKEN ? `?token=${encodeURIComponent(TOKEN)}` : "";
+
+const historialCpu = [];
+const historialRam = [];
+
+function agregarPunto(historial, valor) {
+    historial.push(valor);
+    if (historial.length > MAX_PUNTOS) {
+        historial.shift();
+    }
+}
+
+function dibujarGrafica(id, historial, color) {
+    const canvas = document.getElementById(id);
+    const ctx = canvas.getContext("2d");
+    const { width, height } = canvas;
+
+    ctx.clearRect(0, 0, width, height);
+
+    ctx.strokeStyle = "#eee";
+    for (let y = 0; y <= 100; y += 25) {
+        const py = height - (y / 100) * height;
+        ctx.beginPath();
+        ctx.moveTo(0, py);
+        ctx.lineTo(width, py);
+        ctx.stroke();
+    }
+
+    ctx.strokeStyle = color;
+    ctx.lineWidth = 2;
+    ctx.beginPath();
+    historial.forEach((valor, i) => {
+        const x = (i / (MAX_PUNTOS - 1)) * width;
+        const y = height - (Math.min(valor, 100) / 100) * height;
+        if (i === 0) {
+            ctx.moveTo(x, y);
+        } else {
+            ctx.lineTo(x, y);
+        }
+    });
+    ctx.stroke();
+}
+
+function formatoKb(kb) {
+    return `${(kb / 1024).toFixed(1)} MB`;
+}
+
+function fecha(segundos) {
+    return new Date(segundos * 1000).toLocaleTimeString();
+}
+
+function celda(fila, texto) {
+    const td = document.createElement("td");
+    td.textContent = texto;
+    fila.appendChild(td);
+}
+
+function pintarInstantanea(instantanea) {
+    const sistema = instantanea.system;
+    if (sistema) {
+        document.getElementById("cpu").textContent = `${sistema.CPU_USADA} %`;
+        document.getElementById("ram-ocupada").textContent = formatoKb(sistema.RAM_OCUPADA);
+        document.getElementById("ram-libre").textContent = formatoKb(sistema.RAM_LIBRE);
+        document.getElementById("ram-total").textContent = formatoKb(sistema.RAM_TOTAL);
+
+        agregarPunto(historialCpu, sistema.CPU_USADA);
+        agregarPunto(historialRam, sistema.RAM_TOTAL ? (sistema.RAM_OCUPADA / sistema.RAM_TOTAL) * 100 : 0);
+        dibujarGrafica("grafica-cpu", historialCpu, "#cf222e");
+        dibujarGrafica("grafica-ram", historialRam, "#0969da");
+    }
+
+    const categorias = Object.entries(instantanea.categorias)
+        .sort()
+        .map(([categoria, cantidad]) => `${categoria}: ${cantidad}`);
+    document.getElementById("categorias").textContent = categorias.length ? categorias.join("  ") : "-";
+
+    const tabla = document.getElementById("contenedores");
+    tabla.innerHTML = "";
+    for (const c of instantanea.containers) {
+        const fila = document.createElement("tr");
+        celda(fila, c.pid);
+        celda(fila, c.name);
+        celda(fila, c.categoria || "-");
+        celda(fila, c.cpu_percent);
+        celda(fila, c.memory_percent);
+        celda(fila, c.memory_rss);
+        celda(fila, c.virtual_memory);
+        celda(fila, c.disk_usage);
+        celda(fila, c.io_read_bytes);
+        celda(fila, c.io_write_bytes);
+        tabla.appendChild(fila);
+    }
+
+    agregarEventos(instantanea.eventos);
+}
+
+function agregarEventos(eventos) {
+    const lista = document.getElementById("eventos");
+    for (const evento of eventos) {
+        const li = document.createElement("li");
+        li.textContent = `${fecha(evento.timestamp)}  [${evento.category}]  ${evento.name}  ${evento.action}`;
+        lista.prepend(li);
+    }
+}
+
+async function cargarEstado() {
+    const respuesta = await fetch(`/dashboard/estado${consultaToken}`);
+    const estado = await respuesta.json();
+    agregarEventos(estado.eventos_recientes);
+    if (estado.instantanea) {
+        pintarInstantanea({ ...estado.instantanea, eventos: [] });
+    }
+}
+
+function conectar() {
+    const conexion = document.getElementById("conexion");
+    const fuente = new EventSource(`/dashboard/eventos${consultaToken}`);
+
+    fuente.onopen = () => {
+        conexion.textContent = "conectado";
+        conexion.className = "conectado";
+    };
+    fuente.onerror = () => {
+        conexion.textContent = "desconectado";
+        conexion.className = "desconectado";
+    };
+    fuente.onmessage = (mensaje) => pintarInstantanea(JSON.parse(mensaje.data));
+}
+
+cargarEstado().finally(conectar);